@@ -161,6 +161,24 @@ def build_base_config_v2(
     )
 
 
+def _legacy_pool_configs(
+    base_config: "amm_sim_rs.SimulationConfig",
+) -> list["amm_sim_rs.PoolConfigV2"]:
+    """The single legacy (0, 1) pool, with the legacy reserves and curve."""
+    return [
+        amm_sim_rs.PoolConfigV2(
+            token_a=0,
+            token_b=1,
+            initial_a=base_config.initial_x,
+            initial_b=base_config.initial_y,
+            weight=base_config.pool_weight,
+            amplification=base_config.stableswap_amp,
+            ranges=base_config.liquidity_ranges,
+            pmm_k=base_config.pmm_k,
+        )
+    ]
+
+
 def build_v2_configs_from_legacy(
     *,
     base_config: "amm_sim_rs.SimulationConfig",
@@ -172,7 +190,7 @@ def build_v2_configs_from_legacy(
     Mapping:
     - token 0 = X asset
     - token 1 = Y asset (numeraire)
-    - one direct pool (0,1) with legacy reserves
+    - one direct pool (0,1) with legacy reserves and curve parameters
     """
     import numpy as np
 
@@ -209,6 +227,25 @@ def build_v2_configs_from_legacy(
                 numeraire_token=1,
                 pools=[(0, 1, base_config.initial_x, base_config.initial_y)],
                 seed=i,
+                pool_configs=_legacy_pool_configs(base_config),
+                fee_mode=base_config.fee_mode,
+                protocol_fee=base_config.protocol_fee,
+                retail_exact_out_prob=base_config.retail_exact_out_prob,
+                integer_math=base_config.integer_math,
+                lp_schedule=base_config.lp_schedule,
+                lp_arrival_rate=base_config.lp_arrival_rate,
+                lp_mean_size=base_config.lp_mean_size,
+                lp_sensitivity=base_config.lp_sensitivity,
+                oracle_feed=base_config.oracle_feed,
+                oracle_lag=base_config.oracle_lag,
+                oracle_noise=base_config.oracle_noise,
+                oracle_heartbeat=base_config.oracle_heartbeat,
+                oracle_deviation=base_config.oracle_deviation,
+                markout_horizons=base_config.markout_horizons,
+                gas_price=base_config.gas_price,
+                failure_policy=base_config.failure_policy,
+                failure_fee=base_config.failure_fee,
+                evm=base_config.evm,
             )
         )
     return configs
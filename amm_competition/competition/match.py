@@ -18,6 +18,78 @@ class LightweightStepResult:
     spot_prices: dict[str, float]
     pnls: dict[str, float]
     fees: dict[str, tuple[float, float]]
+    treasury: dict[str, float] = field(default_factory=dict)
+    lp_shares: dict[str, float] = field(default_factory=dict)
+    slots: dict[str, list[float]] = field(default_factory=dict)
+
+
+@dataclass
+class GasStats:
+    """Gas summary of one strategy callback."""
+    count: int
+    total: int
+    min: int
+    mean: float
+    max: int
+    p50: int
+    p90: int
+    p99: int
+
+
+def _convert_gas(rust_gas) -> dict[str, dict[str, GasStats]]:
+    """Convert Rust per-strategy, per-callback gas stats into dataclasses."""
+    return {
+        name: {
+            callback: GasStats(
+                count=g.count,
+                total=g.total,
+                min=g.min,
+                mean=g.mean,
+                max=g.max,
+                p50=g.p50,
+                p90=g.p90,
+                p99=g.p99,
+            )
+            for callback, g in callbacks.items()
+        }
+        for name, callbacks in rust_gas.items()
+    }
+
+
+@dataclass
+class FailureCounts:
+    """Failed strategy callbacks over a simulation."""
+    reverts: int = 0
+    out_of_gas: int = 0
+    invalid: int = 0
+
+
+def _convert_failures(rust_failures) -> dict[str, FailureCounts]:
+    """Convert Rust per-strategy failure counts into dataclasses."""
+    return {
+        name: FailureCounts(
+            reverts=f.reverts,
+            out_of_gas=f.out_of_gas,
+            invalid=f.invalid,
+        )
+        for name, f in rust_failures.items()
+    }
+
+
+def _sim_outcome(edge_a: float, edge_b: float, disqualified) -> int:
+    """Score one simulation: 1 if the submission wins, -1 if the normalizer does, 0 on a draw.
+
+    A disqualified strategy loses outright; if both are, it is a draw.
+    """
+    dq_a = "submission" in disqualified
+    dq_b = "normalizer" in disqualified
+    if dq_a or dq_b:
+        return int(dq_b) - int(dq_a)
+    if edge_a > edge_b:
+        return 1
+    if edge_b > edge_a:
+        return -1
+    return 0
 
 
 @dataclass
@@ -33,6 +105,18 @@ class LightweightSimResult:
     arb_volume_y: dict[str, float]
     retail_volume_y: dict[str, float]
     average_fees: dict[str, tuple[float, float]]
+    protocol_fees: dict[str, tuple[float, float]] = field(default_factory=dict)
+    treasury: dict[str, float] = field(default_factory=dict)
+    pnl_per_share: dict[str, float] = field(default_factory=dict)
+    edges_per_share: dict[str, float] = field(default_factory=dict)
+    lp_shares: dict[str, float] = field(default_factory=dict)
+    lvr: dict[str, float] = field(default_factory=dict)
+    arb_markouts: dict[str, list[tuple[int, float]]] = field(default_factory=dict)
+    retail_markouts: dict[str, list[tuple[int, float]]] = field(default_factory=dict)
+    gas: dict[str, dict[str, GasStats]] = field(default_factory=dict)
+    gas_cost: dict[str, float] = field(default_factory=dict)
+    failures: dict[str, FailureCounts] = field(default_factory=dict)
+    disqualified: dict[str, str] = field(default_factory=dict)
 
 
 @dataclass
@@ -70,6 +154,11 @@ class PoolStateV2:
     token_b: int
     reserve_a: float
     reserve_b: float
+    protocol_fee_a: float = 0.0
+    protocol_fee_b: float = 0.0
+    lp_shares: float = 0.0
+    pnl_per_share: float = 0.0
+    edge_per_share: float = 0.0
 
 
 @dataclass
@@ -81,6 +170,14 @@ class LightweightSimResultV2:
     edges: dict[str, Decimal]
     final_prices: list[float]
     pools: list[PoolStateV2]
+    treasury: dict[str, float] = field(default_factory=dict)
+    lvr: dict[str, float] = field(default_factory=dict)
+    arb_markouts: dict[str, list[tuple[int, float]]] = field(default_factory=dict)
+    retail_markouts: dict[str, list[tuple[int, float]]] = field(default_factory=dict)
+    gas: dict[str, dict[str, GasStats]] = field(default_factory=dict)
+    gas_cost: dict[str, float] = field(default_factory=dict)
+    failures: dict[str, FailureCounts] = field(default_factory=dict)
+    disqualified: dict[str, str] = field(default_factory=dict)
 
 
 @dataclass
@@ -168,6 +265,29 @@ class MatchRunner:
                 retail_size_sigma=self.base_config.retail_size_sigma,
                 retail_buy_prob=self.base_config.retail_buy_prob,
                 seed=i,
+                pool_weight=self.base_config.pool_weight,
+                stableswap_amp=self.base_config.stableswap_amp,
+                liquidity_ranges=self.base_config.liquidity_ranges,
+                pmm_k=self.base_config.pmm_k,
+                oracle_lag=self.base_config.oracle_lag,
+                oracle_noise=self.base_config.oracle_noise,
+                fee_mode=self.base_config.fee_mode,
+                protocol_fee=self.base_config.protocol_fee,
+                retail_exact_out_prob=self.base_config.retail_exact_out_prob,
+                integer_math=self.base_config.integer_math,
+                lp_schedule=self.base_config.lp_schedule,
+                lp_arrival_rate=self.base_config.lp_arrival_rate,
+                lp_mean_size=self.base_config.lp_mean_size,
+                lp_sensitivity=self.base_config.lp_sensitivity,
+                oracle_feed=self.base_config.oracle_feed,
+                oracle_heartbeat=self.base_config.oracle_heartbeat,
+                oracle_deviation=self.base_config.oracle_deviation,
+                markout_horizons=self.base_config.markout_horizons,
+                gas_price=self.base_config.gas_price,
+                record_slots=self.base_config.record_slots,
+                failure_policy=self.base_config.failure_policy,
+                failure_fee=self.base_config.failure_fee,
+                evm=self.base_config.evm,
             )
             configs.append(cfg)
         return configs
@@ -215,9 +335,10 @@ class MatchRunner:
             total_edge_a += Decimal(str(edge_a))
             total_edge_b += Decimal(str(edge_b))
 
-            if edge_a > edge_b:
+            outcome = _sim_outcome(edge_a, edge_b, rust_result.disqualified)
+            if outcome > 0:
                 wins_a += 1
-            elif edge_b > edge_a:
+            elif outcome < 0:
                 wins_b += 1
             else:
                 draws += 1
@@ -231,6 +352,9 @@ class MatchRunner:
                         spot_prices=s.spot_prices,
                         pnls=s.pnls,
                         fees=s.fees,
+                        treasury=s.treasury,
+                        lp_shares=s.lp_shares,
+                        slots=s.slots,
                     )
                     for s in rust_result.steps
                 ]
@@ -248,6 +372,18 @@ class MatchRunner:
                     arb_volume_y=rust_result.arb_volume_y,
                     retail_volume_y=rust_result.retail_volume_y,
                     average_fees=rust_result.average_fees,
+                    protocol_fees=rust_result.protocol_fees,
+                    treasury=rust_result.treasury,
+                    pnl_per_share=rust_result.pnl_per_share,
+                    edges_per_share=rust_result.edges_per_share,
+                    lp_shares=rust_result.lp_shares,
+                    lvr=rust_result.lvr,
+                    arb_markouts=rust_result.arb_markouts,
+                    retail_markouts=rust_result.retail_markouts,
+                    gas=_convert_gas(rust_result.gas),
+                    gas_cost=rust_result.gas_cost,
+                    failures=_convert_failures(rust_result.failures),
+                    disqualified=rust_result.disqualified,
                 )
                 simulation_results.append(sim_result)
 
@@ -333,9 +469,10 @@ class MatchRunnerV2:
             total_edge_a += Decimal(str(edge_a))
             total_edge_b += Decimal(str(edge_b))
 
-            if edge_a > edge_b:
+            outcome = _sim_outcome(edge_a, edge_b, rust_result.disqualified)
+            if outcome > 0:
                 wins_a += 1
-            elif edge_b > edge_a:
+            elif outcome < 0:
                 wins_b += 1
             else:
                 draws += 1
@@ -348,6 +485,11 @@ class MatchRunnerV2:
                         token_b=p.token_b,
                         reserve_a=p.reserve_a,
                         reserve_b=p.reserve_b,
+                        protocol_fee_a=p.protocol_fee_a,
+                        protocol_fee_b=p.protocol_fee_b,
+                        lp_shares=p.lp_shares,
+                        pnl_per_share=p.pnl_per_share,
+                        edge_per_share=p.edge_per_share,
                     )
                     for p in rust_result.pools
                 ]
@@ -359,6 +501,14 @@ class MatchRunnerV2:
                     edges={k: Decimal(str(v)) for k, v in rust_result.edges.items()},
                     final_prices=list(rust_result.final_prices),
                     pools=pools,
+                    treasury=rust_result.treasury,
+                    lvr=rust_result.lvr,
+                    arb_markouts=rust_result.arb_markouts,
+                    retail_markouts=rust_result.retail_markouts,
+                    gas=_convert_gas(rust_result.gas),
+                    gas_cost=rust_result.gas_cost,
+                    failures=_convert_failures(rust_result.failures),
+                    disqualified=rust_result.disqualified,
                 )
                 simulation_results.append(sim_result)
 
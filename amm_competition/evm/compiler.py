@@ -63,6 +63,8 @@ class SolidityCompiler:
             "AMMStrategyBase.sol",
             "IAMMStrategyV2.sol",
             "AMMStrategyBaseV2.sol",
+            "IBeforeSwapHook.sol",
+            "IOnStepHook.sol",
         ]
         for contract in base_contracts:
             src_file = self.CONTRACTS_DIR / "src" / contract
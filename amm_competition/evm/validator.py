@@ -73,6 +73,8 @@ class SolidityValidator:
         "IAMMStrategy.sol",
         "AMMStrategyBaseV2.sol",
         "IAMMStrategyV2.sol",
+        "IBeforeSwapHook.sol",
+        "IOnStepHook.sol",
     }
 
     RESERVED_IDENTIFIERS = {
@@ -80,6 +82,8 @@ class SolidityValidator:
         "AMMStrategyBaseV2",
         "IAMMStrategy",
         "IAMMStrategyV2",
+        "IBeforeSwapHook",
+        "IOnStepHook",
         "TradeInfo",
         "TradeInfoV2",
     }
@@ -1,9 +1,11 @@
 //! Benchmarks for the simulation engine.
 
+use std::time::Instant;
+
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-// Note: Full benchmarks require compiled Solidity bytecode.
-// These benchmarks test the non-EVM components.
+// Note: Full benchmarks require compiled Solidity bytecode. The EVM
+// benchmarks use a hand-assembled contract to measure callback overhead.
 
 fn benchmark_wad_operations(c: &mut Criterion) {
     use amm_sim_rs::types::wad::Wad;
@@ -46,10 +48,7 @@ fn benchmark_trade_info_encoding(c: &mut Criterion) {
     let mut buffer = [0u8; 196];
 
     c.bench_function("trade_info_encode", |bench| {
-        bench.iter(|| {
-            trade.encode_calldata(&mut buffer);
-            black_box(&buffer)
-        })
+        bench.iter(|| trade.encode_calldata(black_box(&mut buffer)))
     });
 
     let trade_v2 = TradeInfoV2 {
@@ -65,10 +64,7 @@ fn benchmark_trade_info_encoding(c: &mut Criterion) {
     };
     let mut buffer_v2 = [0u8; 292];
     c.bench_function("trade_info_v2_encode", |bench| {
-        bench.iter(|| {
-            trade_v2.encode_calldata(&mut buffer_v2);
-            black_box(&buffer_v2)
-        })
+        bench.iter(|| trade_v2.encode_calldata(black_box(&mut buffer_v2)))
     });
 }
 
@@ -82,12 +78,46 @@ fn benchmark_retail_trader(c: &mut Criterion) {
     });
 }
 
+fn benchmark_evm_callbacks(c: &mut Criterion) {
+    use amm_sim_rs::evm::test_support::COUNTER_INIT;
+    use amm_sim_rs::evm::EVMStrategy;
+    use amm_sim_rs::types::trade_info::TradeInfo;
+    use amm_sim_rs::types::wad::Wad;
+
+    let mut strategy = EVMStrategy::new(COUNTER_INIT.to_vec(), "counter".to_string()).unwrap();
+    let trade = TradeInfo::new(
+        true,
+        Wad::from_f64(1.5),
+        Wad::from_f64(1.5),
+        100,
+        Wad::from_f64(1001.5),
+        Wad::from_f64(998.5),
+    );
+
+    // Reset before each batch so the gas recorder does not grow across samples
+    c.bench_function("evm_after_swap", |bench| {
+        bench.iter_custom(|iters| {
+            strategy.reset().unwrap();
+            let start = Instant::now();
+            for _ in 0..iters {
+                black_box(strategy.after_swap(black_box(&trade)).unwrap());
+            }
+            start.elapsed()
+        })
+    });
+
+    c.bench_function("evm_strategy_reset", |bench| {
+        bench.iter(|| strategy.reset().unwrap())
+    });
+}
+
 criterion_group!(
     benches,
     benchmark_wad_operations,
     benchmark_price_process,
     benchmark_trade_info_encoding,
     benchmark_retail_trader,
+    benchmark_evm_callbacks,
 );
 
 criterion_main!(benches);
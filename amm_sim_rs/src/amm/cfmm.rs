@@ -1,12 +1,24 @@
-//! Constant Function Market Maker (x * y = k).
+//! Constant Function Market Maker.
 //!
-//! Implements Uniswap V3/V4-style fee model where fees are collected
-//! into separate buckets rather than being reinvested into liquidity.
-//! This means fees count toward PnL but don't inflate the k constant.
+//! By default implements the Uniswap V3/V4-style fee model where fees are
+//! taken on the input amount and collected into separate buckets rather than
+//! being reinvested into liquidity. This means fees count toward PnL but
+//! don't inflate the k constant. See `FeeMode` for the alternatives.
+//!
+//! The trading invariant is pluggable through `PricingCurve`; pools use
+//! the constant product curve (x * y = k) unless told otherwise.
+//!
+//! Constant product pools can optionally run in integer execution mode,
+//! where balances live in a `WadPool` and every swap is rounded exactly as
+//! an on-chain pool would round it. The f64 fields then mirror those values.
 
-use crate::evm::EVMStrategy;
+use crate::amm::curve::{ConstantProduct, CurveKind, PricingCurve};
+use crate::amm::wad_pool::{WadPool, WadSwap};
+use crate::evm::strategy::{EVMError, PendingState};
+use crate::evm::{slot_to_f64, CallbackTrace, EVMStrategy, GasRecorder};
+use crate::types::result::FailureCounts;
 use crate::types::trade_info::{TradeInfo, TradeInfoV2};
-use crate::types::wad::Wad;
+use crate::types::wad::{Wad, MAX_FEE};
 
 /// Fee quote (bid and ask fees).
 #[derive(Debug, Clone, Copy)]
@@ -28,18 +40,103 @@ impl FeeQuote {
     }
 }
 
+/// How swap fees are charged and where they end up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FeeMode {
+    /// Fee taken from the input token into a separate bucket (V3/V4 style)
+    #[default]
+    Input,
+    /// Fee taken from the output token into a separate bucket
+    Output,
+    /// Fee taken from the input token and left in the reserves, so k grows
+    /// (Uniswap V2 style)
+    Compounding,
+}
+
+impl FeeMode {
+    /// Parse a config value: "input", "output" or "compounding".
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "input" => Ok(FeeMode::Input),
+            "output" => Ok(FeeMode::Output),
+            "compounding" => Ok(FeeMode::Compounding),
+            other => Err(format!(
+                "unknown fee mode '{}' (expected 'input', 'output' or 'compounding')",
+                other
+            )),
+        }
+    }
+
+    /// Whether the fee is deducted from the input amount before it reaches the curve.
+    #[inline]
+    pub fn fee_on_input(self) -> bool {
+        !matches!(self, FeeMode::Output)
+    }
+}
+
+/// What a pool does when a strategy callback fails during trading.
+///
+/// Failures are reverts, running out of gas, and undecodable return data
+/// from `afterSwap`, `beforeSwap` and `onStep`. Failed quotes are not
+/// failures: the trade that follows calls the strategy again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailurePolicy {
+    /// Keep the fees from the last successful callback
+    #[default]
+    Keep,
+    /// Post this fee on both sides
+    DefaultFee(Wad),
+    /// Post the maximum fee on both sides
+    MaxFee,
+    /// Fail the simulation with the callback's error
+    Disqualify,
+}
+
+impl FailurePolicy {
+    /// Parse a config value: "keep", "default", "max_fee" or "disqualify".
+    ///
+    /// `default_fee` is the fee posted under "default".
+    pub fn parse(value: &str, default_fee: f64) -> Result<Self, String> {
+        match value {
+            "keep" => Ok(FailurePolicy::Keep),
+            "default" => Ok(FailurePolicy::DefaultFee(
+                Wad::from_f64(default_fee).clamp_fee(),
+            )),
+            "max_fee" => Ok(FailurePolicy::MaxFee),
+            "disqualify" => Ok(FailurePolicy::Disqualify),
+            other => Err(format!(
+                "unknown failure policy '{}' (expected 'keep', 'default', 'max_fee' or 'disqualify')",
+                other
+            )),
+        }
+    }
+}
+
 /// Result of a trade execution.
 #[derive(Debug, Clone)]
 pub struct TradeResult {
     pub trade_info: TradeInfo,
+    /// Fee charged, in the input token (or the output token under `FeeMode::Output`)
     pub fee_amount: f64,
 }
 
+/// Result of a liquidity deposit or withdrawal.
+#[derive(Debug, Clone, Copy)]
+pub struct LiquidityChange {
+    /// LP shares minted (deposit) or burned (withdrawal)
+    pub shares: f64,
+    /// X paid in or out
+    pub amount_x: f64,
+    /// Y paid in or out
+    pub amount_y: f64,
+}
+
 /// Constant Function Market Maker with dynamic fees.
 ///
-/// Implements x * y = k invariant with strategy-determined fees.
-/// Uses Uniswap V3/V4 fee model where fees are collected separately
-/// (not reinvested into liquidity).
+/// Delegates invariant math to a `PricingCurve` and applies
+/// strategy-determined fees on top of it according to its `FeeMode`.
+/// Quotes are always in trader-facing amounts: what the trader sends
+/// and what the trader receives.
 pub struct CFMM {
     /// Pool identifier (used in multi-asset mode)
     pub pool_id: usize,
@@ -51,6 +148,16 @@ pub struct CFMM {
     pub name: String,
     /// EVM strategy for fee decisions
     strategy: EVMStrategy,
+    /// Trading invariant
+    curve: Box<dyn PricingCurve>,
+    /// Fee accounting model
+    fee_mode: FeeMode,
+    /// Response to failed strategy callbacks
+    failure_policy: FailurePolicy,
+    /// Failed strategy callbacks since the last reset
+    failures: FailureCounts,
+    /// First failure under `FailurePolicy::Disqualify`, until taken
+    disqualification: Option<EVMError>,
     /// Current X reserves
     reserve_x: f64,
     /// Current Y reserves
@@ -65,6 +172,16 @@ pub struct CFMM {
     accumulated_fees_x: f64,
     /// Accumulated fees in Y (collected separately, not in reserves)
     accumulated_fees_y: f64,
+    /// Share of every fee paid to the protocol, in [0, 1]
+    protocol_fee_fraction: f64,
+    /// Protocol (treasury) fees in X
+    protocol_fees_x: f64,
+    /// Protocol (treasury) fees in Y
+    protocol_fees_y: f64,
+    /// Integer balances, set in integer execution mode
+    wad_pool: Option<WadPool>,
+    /// Outstanding LP shares
+    total_shares: f64,
 }
 
 impl CFMM {
@@ -81,6 +198,27 @@ impl CFMM {
         token_a: usize,
         token_b: usize,
         pool_id: usize,
+    ) -> Self {
+        Self::new_with_curve(
+            strategy,
+            Box::new(ConstantProduct),
+            reserve_x,
+            reserve_y,
+            token_a,
+            token_b,
+            pool_id,
+        )
+    }
+
+    /// Create a new CFMM with a custom pricing curve and token pair metadata.
+    pub fn new_with_curve(
+        strategy: EVMStrategy,
+        curve: Box<dyn PricingCurve>,
+        reserve_x: f64,
+        reserve_y: f64,
+        token_a: usize,
+        token_b: usize,
+        pool_id: usize,
     ) -> Self {
         let name = strategy.name().to_string();
         Self {
@@ -89,6 +227,11 @@ impl CFMM {
             token_b,
             name,
             strategy,
+            curve,
+            fee_mode: FeeMode::default(),
+            failure_policy: FailurePolicy::default(),
+            failures: FailureCounts::default(),
+            disqualification: None,
             reserve_x,
             reserve_y,
             current_fees: FeeQuote::symmetric(Wad::from_bps(30)),
@@ -96,6 +239,11 @@ impl CFMM {
             use_v2_callbacks: false,
             accumulated_fees_x: 0.0,
             accumulated_fees_y: 0.0,
+            protocol_fee_fraction: 0.0,
+            protocol_fees_x: 0.0,
+            protocol_fees_y: 0.0,
+            wad_pool: None,
+            total_shares: (reserve_x * reserve_y).sqrt(),
         }
     }
 
@@ -107,8 +255,7 @@ impl CFMM {
 
     /// Initialize the AMM and get starting fees from strategy.
     pub fn initialize(&mut self) -> Result<(), crate::evm::strategy::EVMError> {
-        let initial_x = Wad::from_f64(self.reserve_x);
-        let initial_y = Wad::from_f64(self.reserve_y);
+        let (initial_x, initial_y) = self.wad_reserves();
 
         let (bid_fee, ask_fee) = self.strategy.after_initialize(initial_x, initial_y)?;
         self.current_fees = FeeQuote::new(bid_fee.clamp_fee(), ask_fee.clamp_fee());
@@ -118,25 +265,24 @@ impl CFMM {
         Ok(())
     }
 
-    /// Initialize using V2 callback with context, with fallback to V1.
+    /// Initialize using the V2 callback with context, or the V1 callback
+    /// for strategies that don't implement V2.
     pub fn initialize_v2_or_fallback(&mut self) -> Result<(), crate::evm::strategy::EVMError> {
-        let initial_a = Wad::from_f64(self.reserve_x);
-        let initial_b = Wad::from_f64(self.reserve_y);
-        match self.strategy.after_initialize_v2(
+        if !self.strategy.implements_v2() {
+            return self.initialize();
+        }
+        let (initial_a, initial_b) = self.wad_reserves();
+        let (bid_fee, ask_fee) = self.strategy.after_initialize_v2(
             initial_a,
             initial_b,
             self.pool_id as u64,
             self.token_a as u64,
             self.token_b as u64,
-        ) {
-            Ok((bid_fee, ask_fee)) => {
-                self.current_fees = FeeQuote::new(bid_fee.clamp_fee(), ask_fee.clamp_fee());
-                self.initialized = true;
-                self.use_v2_callbacks = true;
-                Ok(())
-            }
-            Err(_) => self.initialize(),
-        }
+        )?;
+        self.current_fees = FeeQuote::new(bid_fee.clamp_fee(), ask_fee.clamp_fee());
+        self.initialized = true;
+        self.use_v2_callbacks = true;
+        Ok(())
     }
 
     /// Get current reserves.
@@ -144,17 +290,131 @@ impl CFMM {
         (self.reserve_x, self.reserve_y)
     }
 
+    /// Current reserves as WAD, exact in integer execution mode.
+    fn wad_reserves(&self) -> (Wad, Wad) {
+        match &self.wad_pool {
+            Some(pool) => (pool.reserve_x, pool.reserve_y),
+            None => (Wad::from_f64(self.reserve_x), Wad::from_f64(self.reserve_y)),
+        }
+    }
+
+    /// Get the pricing curve.
+    pub fn curve(&self) -> &dyn PricingCurve {
+        self.curve.as_ref()
+    }
+
+    /// Get the fee accounting model.
+    pub fn fee_mode(&self) -> FeeMode {
+        self.fee_mode
+    }
+
+    /// Set the fee accounting model.
+    pub fn set_fee_mode(&mut self, fee_mode: FeeMode) {
+        self.fee_mode = fee_mode;
+    }
+
+    /// Set the response to failed strategy callbacks.
+    pub fn set_failure_policy(&mut self, policy: FailurePolicy) {
+        self.failure_policy = policy;
+    }
+
+    /// Failed strategy callbacks since the last reset.
+    pub fn failures(&self) -> FailureCounts {
+        self.failures
+    }
+
+    /// Take the failure that disqualified the strategy, if any.
+    ///
+    /// Engines check this after every step and end the simulation with it.
+    pub fn take_disqualification(&mut self) -> Option<EVMError> {
+        self.disqualification.take()
+    }
+
+    /// Count a failed callback and apply the failure policy.
+    fn on_failure(&mut self, error: EVMError) {
+        match error {
+            EVMError::Reverted(_) => self.failures.reverts += 1,
+            EVMError::OutOfGas => self.failures.out_of_gas += 1,
+            _ => self.failures.invalid += 1,
+        }
+        match self.failure_policy {
+            FailurePolicy::Keep => {}
+            FailurePolicy::DefaultFee(fee) => self.current_fees = FeeQuote::symmetric(fee),
+            FailurePolicy::MaxFee => self.current_fees = FeeQuote::symmetric(Wad(MAX_FEE)),
+            FailurePolicy::Disqualify => {
+                self.disqualification.get_or_insert(error);
+            }
+        }
+    }
+
+    /// Get the share of every fee paid to the protocol.
+    pub fn protocol_fee_fraction(&self) -> f64 {
+        self.protocol_fee_fraction
+    }
+
+    /// Set the share of every fee paid to the protocol. Must be in [0, 1].
+    pub fn set_protocol_fee_fraction(&mut self, fraction: f64) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(format!(
+                "protocol fee fraction must be in [0, 1], got {}",
+                fraction
+            ));
+        }
+        self.protocol_fee_fraction = fraction;
+        Ok(())
+    }
+
+    /// Switch to integer execution mode with on-chain rounding.
+    ///
+    /// Only supported on the constant product curve. The current reserves
+    /// are truncated to WAD and become the integer balances.
+    pub fn enable_integer_math(&mut self) -> Result<(), String> {
+        if self.curve.kind() != CurveKind::ConstantProduct {
+            return Err(format!(
+                "integer execution mode requires the constant product curve, got {:?}",
+                self.curve.kind()
+            ));
+        }
+        self.wad_pool = Some(WadPool::new(
+            Wad::from_f64(self.reserve_x),
+            Wad::from_f64(self.reserve_y),
+        ));
+        self.sync_from_wad();
+        Ok(())
+    }
+
+    /// Whether the pool runs in integer execution mode.
+    pub fn integer_math(&self) -> bool {
+        self.wad_pool.is_some()
+    }
+
+    /// Integer balances, if running in integer execution mode.
+    pub fn wad_pool(&self) -> Option<&WadPool> {
+        self.wad_pool.as_ref()
+    }
+
+    /// Pass the current oracle price to the pricing curve.
+    pub fn set_oracle_price(&mut self, price: f64) {
+        self.curve.set_oracle_price(price);
+    }
+
+    /// Publish an oracle round to the strategy (price in Y per X).
+    pub fn publish_oracle(
+        &mut self,
+        price: f64,
+        updated_at: u64,
+    ) -> Result<(), crate::evm::strategy::EVMError> {
+        self.strategy.set_oracle(Wad::from_f64(price), updated_at)
+    }
+
     /// Get current spot price (Y per X).
     pub fn spot_price(&self) -> f64 {
-        if self.reserve_x == 0.0 {
-            return 0.0;
-        }
-        self.reserve_y / self.reserve_x
+        self.curve.spot_price(self.reserve_x, self.reserve_y)
     }
 
-    /// Get current k (constant product).
+    /// Get current k (value of the curve invariant).
     pub fn k(&self) -> f64 {
-        self.reserve_x * self.reserve_y
+        self.curve.invariant(self.reserve_x, self.reserve_y)
     }
 
     /// Get current fees.
@@ -162,31 +422,264 @@ impl CFMM {
         self.current_fees
     }
 
-    /// Get accumulated fees (collected separately from reserves).
+    /// Get accumulated LP fees (collected separately from reserves).
+    ///
+    /// Always zero under `FeeMode::Compounding`, where LP fees stay in the
+    /// reserves. Excludes the protocol share.
     pub fn accumulated_fees(&self) -> (f64, f64) {
         (self.accumulated_fees_x, self.accumulated_fees_y)
     }
 
+    /// Get accumulated protocol (treasury) fees.
+    pub fn protocol_fees(&self) -> (f64, f64) {
+        (self.protocol_fees_x, self.protocol_fees_y)
+    }
+
+    /// Outstanding LP shares. The initial supply is sqrt(x * y).
+    pub fn total_shares(&self) -> f64 {
+        self.total_shares
+    }
+
+    /// Balances owned by LP shares: reserves plus separately collected LP fees.
+    pub fn lp_balances(&self) -> (f64, f64) {
+        (
+            self.reserve_x + self.accumulated_fees_x,
+            self.reserve_y + self.accumulated_fees_y,
+        )
+    }
+
+    /// Deposit liquidity pro rata, spending at most `amount_x` and `amount_y`.
+    ///
+    /// LP fees collected outside the reserves belong to share holders, so the
+    /// deposit tops up the fee buckets in proportion too. Mints shares in
+    /// proportion to the balances added.
+    pub fn add_liquidity(&mut self, amount_x: f64, amount_y: f64) -> Option<LiquidityChange> {
+        let (balance_x, balance_y) = self.lp_balances();
+        let fraction = (amount_x / balance_x).min(amount_y / balance_y);
+        if !(fraction > 0.0 && fraction.is_finite()) {
+            return None;
+        }
+
+        let (paid_x, paid_y) = match self.wad_pool.as_mut() {
+            Some(pool) => {
+                let (x, y) = pool.deposit(Wad::from_f64(fraction))?;
+                self.sync_from_wad();
+                (x.to_f64(), y.to_f64())
+            }
+            None => {
+                self.scale_lp_balances(1.0 + fraction);
+                (balance_x * fraction, balance_y * fraction)
+            }
+        };
+        self.curve.rescale_liquidity(1.0 + fraction);
+
+        let shares = self.total_shares * fraction;
+        self.total_shares += shares;
+        Some(LiquidityChange {
+            shares,
+            amount_x: paid_x,
+            amount_y: paid_y,
+        })
+    }
+
+    /// Burn `shares` LP shares for their pro rata reserves and LP fees.
+    ///
+    /// The last share cannot be withdrawn: the pool must keep liquidity.
+    pub fn remove_liquidity(&mut self, shares: f64) -> Option<LiquidityChange> {
+        let fraction = shares / self.total_shares;
+        if !(fraction > 0.0 && fraction < 1.0) {
+            return None;
+        }
+
+        let (paid_x, paid_y) = match self.wad_pool.as_mut() {
+            Some(pool) => {
+                let (x, y) = pool.withdraw(Wad::from_f64(fraction))?;
+                self.sync_from_wad();
+                (x.to_f64(), y.to_f64())
+            }
+            None => {
+                let (balance_x, balance_y) = self.lp_balances();
+                self.scale_lp_balances(1.0 - fraction);
+                (balance_x * fraction, balance_y * fraction)
+            }
+        };
+        self.curve.rescale_liquidity(1.0 - fraction);
+
+        self.total_shares -= shares;
+        Some(LiquidityChange {
+            shares,
+            amount_x: paid_x,
+            amount_y: paid_y,
+        })
+    }
+
+    fn scale_lp_balances(&mut self, factor: f64) {
+        self.reserve_x *= factor;
+        self.reserve_y *= factor;
+        self.accumulated_fees_x *= factor;
+        self.accumulated_fees_y *= factor;
+    }
+
+    /// Gas used by the strategy's callbacks since the last reset.
+    pub fn gas(&self) -> &GasRecorder {
+        self.strategy.gas()
+    }
+
+    /// The strategy's `slots` array, each value read as an int256.
+    pub fn strategy_slots(&self) -> Result<Vec<f64>, crate::evm::strategy::EVMError> {
+        Ok(self
+            .strategy
+            .slots()?
+            .into_iter()
+            .map(slot_to_f64)
+            .collect())
+    }
+
+    /// Take the strategy's callback traces (empty unless tracing is enabled).
+    pub fn take_traces(&mut self) -> Vec<CallbackTrace> {
+        self.strategy.take_traces()
+    }
+
+    /// Whether the strategy prices each trade through a `beforeSwap` hook.
+    pub fn has_before_swap(&self) -> bool {
+        self.strategy.implements_before_swap()
+    }
+
+    /// Fee the pool would charge on a trade with `amount_in` of input.
+    ///
+    /// `is_buy` is true when the AMM buys X (bid side). Without a
+    /// `beforeSwap` hook this is the current bid or ask fee; with one it is
+    /// the hook's answer, evaluated without committing strategy state.
+    pub fn fee_for(&self, is_buy: bool, amount_in: f64) -> Wad {
+        let fallback = self.posted_fee(is_buy);
+        if !self.has_before_swap() || amount_in <= 0.0 {
+            return fallback;
+        }
+        let (reserve_x, reserve_y) = self.wad_reserves();
+        self.strategy
+            .quote_before_swap(is_buy, Wad::from_f64(amount_in), reserve_x, reserve_y)
+            .map(Wad::clamp_fee)
+            .unwrap_or(fallback)
+    }
+
+    /// Posted bid (AMM buys X) or ask fee.
+    #[inline]
+    fn posted_fee(&self, is_buy: bool) -> Wad {
+        if is_buy {
+            self.current_fees.bid_fee
+        } else {
+            self.current_fees.ask_fee
+        }
+    }
+
+    /// Hook fee for an exact-output trade, whose input depends on the fee.
+    ///
+    /// Sizes the input at the posted fee, then asks the hook once.
+    fn fee_for_exact_out(
+        &self,
+        is_buy: bool,
+        amount_out: f64,
+        quote_at: fn(&Self, f64, Wad) -> (f64, f64),
+    ) -> Wad {
+        let posted = self.posted_fee(is_buy);
+        if !self.has_before_swap() {
+            return posted;
+        }
+        let (amount_in, _) = quote_at(self, amount_out, posted);
+        self.fee_for(is_buy, amount_in)
+    }
+
+    /// Call `beforeSwap` for a trade about to execute.
+    ///
+    /// Uses the posted fee if the strategy has no hook, and the posted fee
+    /// after applying the failure policy if the hook fails. The hook's state
+    /// is returned uncommitted; `commit_before_swap` applies it once the
+    /// trade executes.
+    fn before_swap_fee(
+        &mut self,
+        is_buy: bool,
+        amount_in: f64,
+        timestamp: u64,
+    ) -> (Wad, Option<PendingState>) {
+        if !self.has_before_swap() || amount_in <= 0.0 {
+            return (self.posted_fee(is_buy), None);
+        }
+        let (reserve_x, reserve_y) = self.wad_reserves();
+        match self.strategy.before_swap(
+            is_buy,
+            Wad::from_f64(amount_in),
+            reserve_x,
+            reserve_y,
+            timestamp,
+        ) {
+            Ok((fee, pending)) => (fee.clamp_fee(), Some(pending)),
+            Err(error) => {
+                self.on_failure(error);
+                (self.posted_fee(is_buy), None)
+            }
+        }
+    }
+
+    /// `before_swap_fee` for an exact-output trade, sizing the input the
+    /// same way `fee_for_exact_out` does so quotes and execution agree.
+    fn before_swap_fee_exact_out(
+        &mut self,
+        is_buy: bool,
+        amount_out: f64,
+        quote_at: fn(&Self, f64, Wad) -> (f64, f64),
+        timestamp: u64,
+    ) -> (Wad, Option<PendingState>) {
+        if !self.has_before_swap() {
+            return (self.posted_fee(is_buy), None);
+        }
+        let (amount_in, _) = quote_at(self, amount_out, self.posted_fee(is_buy));
+        self.before_swap_fee(is_buy, amount_in, timestamp)
+    }
+
+    /// Apply the state a `beforeSwap` hook wrote for a trade that executed.
+    fn commit_before_swap(&mut self, pending: Option<PendingState>) {
+        if let Some(pending) = pending {
+            self.strategy.commit(pending);
+        }
+    }
+
     /// Fast quote for AMM buying X (trader selling X).
     ///
     /// Returns (y_out, fee_amount) or (0, 0) if invalid.
     #[inline]
     pub fn quote_buy_x(&self, amount_x: f64) -> (f64, f64) {
+        self.quote_buy_x_at(amount_x, self.fee_for(true, amount_x))
+    }
+
+    /// `quote_buy_x` at an explicit fee.
+    fn quote_buy_x_at(&self, amount_x: f64, fee: Wad) -> (f64, f64) {
+        if self.wad_pool.is_some() {
+            return self.quote_wad(true, true, amount_x, fee);
+        }
         if amount_x <= 0.0 {
             return (0.0, 0.0);
         }
 
-        let fee = self.current_fees.bid_fee.to_f64();
+        let fee = fee.to_f64();
         let gamma = (1.0 - fee).clamp(0.0, 1.0);
         if gamma <= 0.0 {
             return (0.0, 0.0);
         }
-        let net_x = amount_x * gamma;
 
-        let k = self.reserve_x * self.reserve_y;
-        let new_rx = self.reserve_x + net_x;
-        let new_ry = k / new_rx;
-        let y_out = self.reserve_y - new_ry;
+        if !self.fee_mode.fee_on_input() {
+            let gross_y = self
+                .curve
+                .y_out_for_x_in(self.reserve_x, self.reserve_y, amount_x);
+            if gross_y > 0.0 {
+                return (gross_y * gamma, gross_y * fee);
+            }
+            return (0.0, 0.0);
+        }
+
+        let net_x = amount_x * gamma;
+        let y_out = self
+            .curve
+            .y_out_for_x_in(self.reserve_x, self.reserve_y, net_x);
 
         if y_out > 0.0 {
             (y_out, amount_x * fee)
@@ -200,22 +693,47 @@ impl CFMM {
     /// Returns (total_y_in, fee_amount) or (0, 0) if invalid.
     #[inline]
     pub fn quote_sell_x(&self, amount_x: f64) -> (f64, f64) {
+        self.quote_sell_x_at(
+            amount_x,
+            self.fee_for_exact_out(false, amount_x, Self::quote_sell_x_at),
+        )
+    }
+
+    /// `quote_sell_x` at an explicit fee.
+    fn quote_sell_x_at(&self, amount_x: f64, fee: Wad) -> (f64, f64) {
+        if self.wad_pool.is_some() {
+            return self.quote_wad(false, false, amount_x, fee);
+        }
         if amount_x <= 0.0 || amount_x >= self.reserve_x {
             return (0.0, 0.0);
         }
 
-        let k = self.reserve_x * self.reserve_y;
-        let fee = self.current_fees.ask_fee.to_f64();
+        let fee = fee.to_f64();
         let gamma = (1.0 - fee).clamp(0.0, 1.0);
         if gamma <= 0.0 {
             return (0.0, 0.0);
         }
 
-        let new_rx = self.reserve_x - amount_x;
-        let new_ry = k / new_rx;
-        let net_y = new_ry - self.reserve_y;
+        if !self.fee_mode.fee_on_input() {
+            // The pool releases the gross amount; the fee is withheld in X.
+            let gross_x = amount_x / gamma;
+            if gross_x >= self.reserve_x {
+                return (0.0, 0.0);
+            }
+            let total_y = self
+                .curve
+                .y_in_for_x_out(self.reserve_x, self.reserve_y, gross_x);
+            if total_y <= 0.0 || !total_y.is_finite() {
+                return (0.0, 0.0);
+            }
+            return (total_y, gross_x - amount_x);
+        }
+
+        let net_y = self
+            .curve
+            .y_in_for_x_out(self.reserve_x, self.reserve_y, amount_x);
 
-        if net_y <= 0.0 {
+        if net_y <= 0.0 || !net_y.is_finite() {
             return (0.0, 0.0);
         }
 
@@ -228,21 +746,38 @@ impl CFMM {
     /// Returns (x_out, fee_amount) or (0, 0) if invalid.
     #[inline]
     pub fn quote_x_for_y(&self, amount_y: f64) -> (f64, f64) {
+        self.quote_x_for_y_at(amount_y, self.fee_for(false, amount_y))
+    }
+
+    /// `quote_x_for_y` at an explicit fee.
+    fn quote_x_for_y_at(&self, amount_y: f64, fee: Wad) -> (f64, f64) {
+        if self.wad_pool.is_some() {
+            return self.quote_wad(false, true, amount_y, fee);
+        }
         if amount_y <= 0.0 {
             return (0.0, 0.0);
         }
 
-        let k = self.reserve_x * self.reserve_y;
-        let fee = self.current_fees.ask_fee.to_f64();
+        let fee = fee.to_f64();
         let gamma = (1.0 - fee).clamp(0.0, 1.0);
         if gamma <= 0.0 {
             return (0.0, 0.0);
         }
 
+        if !self.fee_mode.fee_on_input() {
+            let gross_x = self
+                .curve
+                .x_out_for_y_in(self.reserve_x, self.reserve_y, amount_y);
+            if gross_x > 0.0 {
+                return (gross_x * gamma, gross_x * fee);
+            }
+            return (0.0, 0.0);
+        }
+
         let net_y = amount_y * gamma;
-        let new_ry = self.reserve_y + net_y;
-        let new_rx = k / new_ry;
-        let x_out = self.reserve_x - new_rx;
+        let x_out = self
+            .curve
+            .x_out_for_y_in(self.reserve_x, self.reserve_y, net_y);
 
         if x_out > 0.0 {
             (x_out, amount_y * fee)
@@ -251,9 +786,63 @@ impl CFMM {
         }
     }
 
+    /// Fast quote for AMM buying X with an exact Y output (trader sells X).
+    ///
+    /// Returns (total_x_in, fee_amount) or (0, 0) if invalid.
+    #[inline]
+    pub fn quote_buy_x_for_y(&self, amount_y: f64) -> (f64, f64) {
+        self.quote_buy_x_for_y_at(
+            amount_y,
+            self.fee_for_exact_out(true, amount_y, Self::quote_buy_x_for_y_at),
+        )
+    }
+
+    /// `quote_buy_x_for_y` at an explicit fee.
+    fn quote_buy_x_for_y_at(&self, amount_y: f64, fee: Wad) -> (f64, f64) {
+        if self.wad_pool.is_some() {
+            return self.quote_wad(true, false, amount_y, fee);
+        }
+        if amount_y <= 0.0 || amount_y >= self.reserve_y {
+            return (0.0, 0.0);
+        }
+
+        let fee = fee.to_f64();
+        let gamma = (1.0 - fee).clamp(0.0, 1.0);
+        if gamma <= 0.0 {
+            return (0.0, 0.0);
+        }
+
+        if !self.fee_mode.fee_on_input() {
+            // The pool releases the gross amount; the fee is withheld in Y.
+            let gross_y = amount_y / gamma;
+            if gross_y >= self.reserve_y {
+                return (0.0, 0.0);
+            }
+            let total_x = self
+                .curve
+                .x_in_for_y_out(self.reserve_x, self.reserve_y, gross_y);
+            if total_x <= 0.0 || !total_x.is_finite() {
+                return (0.0, 0.0);
+            }
+            return (total_x, gross_y - amount_y);
+        }
+
+        let net_x = self
+            .curve
+            .x_in_for_y_out(self.reserve_x, self.reserve_y, amount_y);
+
+        if net_x <= 0.0 || !net_x.is_finite() {
+            return (0.0, 0.0);
+        }
+
+        let total_x = net_x / gamma;
+        (total_x, total_x - net_x)
+    }
+
     /// Quote exact-input swap for arbitrary token direction.
     ///
-    /// Returns (amount_out, fee_amount_in_token_in).
+    /// Returns (amount_out, fee_amount); the fee is in token_in, or in
+    /// token_out under `FeeMode::Output`.
     #[inline]
     pub fn quote_exact_in(
         &self,
@@ -275,18 +864,52 @@ impl CFMM {
         None
     }
 
+    /// Quote exact-output swap for arbitrary token direction.
+    ///
+    /// Returns (amount_in, fee_amount); the fee is in token_in, or in
+    /// token_out under `FeeMode::Output`.
+    #[inline]
+    pub fn quote_exact_out(
+        &self,
+        token_in: usize,
+        token_out: usize,
+        amount_out: f64,
+    ) -> Option<(f64, f64)> {
+        if amount_out <= 0.0 || !self.supports_pair(token_in, token_out) {
+            return None;
+        }
+        let (amount_in, fee) = if token_in == self.token_a {
+            self.quote_buy_x_for_y(amount_out)
+        } else {
+            self.quote_sell_x(amount_out)
+        };
+        if amount_in > 0.0 {
+            Some((amount_in, fee))
+        } else {
+            None
+        }
+    }
+
     /// Execute trade where AMM buys X (trader sells X for Y).
     pub fn execute_buy_x(&mut self, amount_x: f64, timestamp: u64) -> Option<TradeResult> {
-        let (y_out, fee_amount) = self.quote_buy_x(amount_x);
+        let (fee, pending) = self.before_swap_fee(true, amount_x, timestamp);
+        if self.wad_pool.is_some() {
+            return self.execute_wad(true, true, amount_x, fee, pending, timestamp);
+        }
+        let (y_out, fee_amount) = self.quote_buy_x_at(amount_x, fee);
         if y_out <= 0.0 {
             return None;
         }
 
-        // Update reserves - fees go to separate bucket, not into liquidity
-        let net_x = amount_x - fee_amount;
-        self.reserve_x += net_x;
-        self.accumulated_fees_x += fee_amount;
-        self.reserve_y -= y_out;
+        if self.fee_mode.fee_on_input() {
+            self.reserve_x += amount_x - fee_amount;
+            self.reserve_y -= y_out;
+            self.credit_fee(fee_amount, true);
+        } else {
+            self.reserve_x += amount_x;
+            self.reserve_y -= y_out + fee_amount;
+            self.credit_fee(fee_amount, false);
+        }
 
         let trade_info = TradeInfo::new(
             true, // is_buy (AMM buys X)
@@ -297,7 +920,8 @@ impl CFMM {
             Wad::from_f64(self.reserve_y),
         );
 
-        // Update fees from strategy
+        // Commit the hook's state, then update fees from strategy
+        self.commit_before_swap(pending);
         self.update_fees(&trade_info);
 
         Some(TradeResult {
@@ -308,16 +932,25 @@ impl CFMM {
 
     /// Execute trade where AMM sells X (trader buys X with Y).
     pub fn execute_sell_x(&mut self, amount_x: f64, timestamp: u64) -> Option<TradeResult> {
-        let (total_y, fee_amount) = self.quote_sell_x(amount_x);
+        let (fee, pending) =
+            self.before_swap_fee_exact_out(false, amount_x, Self::quote_sell_x_at, timestamp);
+        if self.wad_pool.is_some() {
+            return self.execute_wad(false, false, amount_x, fee, pending, timestamp);
+        }
+        let (total_y, fee_amount) = self.quote_sell_x_at(amount_x, fee);
         if total_y <= 0.0 {
             return None;
         }
 
-        // Update reserves - fees go to separate bucket, not into liquidity
-        let net_y = total_y - fee_amount;
-        self.reserve_x -= amount_x;
-        self.reserve_y += net_y;
-        self.accumulated_fees_y += fee_amount;
+        if self.fee_mode.fee_on_input() {
+            self.reserve_x -= amount_x;
+            self.reserve_y += total_y - fee_amount;
+            self.credit_fee(fee_amount, false);
+        } else {
+            self.reserve_x -= amount_x + fee_amount;
+            self.reserve_y += total_y;
+            self.credit_fee(fee_amount, true);
+        }
 
         let trade_info = TradeInfo::new(
             false, // is_buy = false (AMM sells X)
@@ -328,7 +961,8 @@ impl CFMM {
             Wad::from_f64(self.reserve_y),
         );
 
-        // Update fees from strategy
+        // Commit the hook's state, then update fees from strategy
+        self.commit_before_swap(pending);
         self.update_fees(&trade_info);
 
         Some(TradeResult {
@@ -339,16 +973,24 @@ impl CFMM {
 
     /// Execute trade where trader pays Y to receive X.
     pub fn execute_buy_x_with_y(&mut self, amount_y: f64, timestamp: u64) -> Option<TradeResult> {
-        let (x_out, fee_amount) = self.quote_x_for_y(amount_y);
+        let (fee, pending) = self.before_swap_fee(false, amount_y, timestamp);
+        if self.wad_pool.is_some() {
+            return self.execute_wad(false, true, amount_y, fee, pending, timestamp);
+        }
+        let (x_out, fee_amount) = self.quote_x_for_y_at(amount_y, fee);
         if x_out <= 0.0 {
             return None;
         }
 
-        // Update reserves - fees go to separate bucket, not into liquidity
-        let net_y = amount_y - fee_amount;
-        self.reserve_x -= x_out;
-        self.reserve_y += net_y;
-        self.accumulated_fees_y += fee_amount;
+        if self.fee_mode.fee_on_input() {
+            self.reserve_x -= x_out;
+            self.reserve_y += amount_y - fee_amount;
+            self.credit_fee(fee_amount, false);
+        } else {
+            self.reserve_x -= x_out + fee_amount;
+            self.reserve_y += amount_y;
+            self.credit_fee(fee_amount, true);
+        }
 
         let trade_info = TradeInfo::new(
             false, // is_buy = false (AMM sells X)
@@ -359,7 +1001,49 @@ impl CFMM {
             Wad::from_f64(self.reserve_y),
         );
 
-        // Update fees from strategy
+        // Commit the hook's state, then update fees from strategy
+        self.commit_before_swap(pending);
+        self.update_fees(&trade_info);
+
+        Some(TradeResult {
+            trade_info,
+            fee_amount,
+        })
+    }
+
+    /// Execute trade where AMM buys X, paying out exactly `amount_y` (trader sells X).
+    pub fn execute_buy_x_for_y(&mut self, amount_y: f64, timestamp: u64) -> Option<TradeResult> {
+        let (fee, pending) =
+            self.before_swap_fee_exact_out(true, amount_y, Self::quote_buy_x_for_y_at, timestamp);
+        if self.wad_pool.is_some() {
+            return self.execute_wad(true, false, amount_y, fee, pending, timestamp);
+        }
+        let (total_x, fee_amount) = self.quote_buy_x_for_y_at(amount_y, fee);
+        if total_x <= 0.0 {
+            return None;
+        }
+
+        if self.fee_mode.fee_on_input() {
+            self.reserve_x += total_x - fee_amount;
+            self.reserve_y -= amount_y;
+            self.credit_fee(fee_amount, true);
+        } else {
+            self.reserve_x += total_x;
+            self.reserve_y -= amount_y + fee_amount;
+            self.credit_fee(fee_amount, false);
+        }
+
+        let trade_info = TradeInfo::new(
+            true, // is_buy (AMM buys X)
+            Wad::from_f64(total_x),
+            Wad::from_f64(amount_y),
+            timestamp,
+            Wad::from_f64(self.reserve_x),
+            Wad::from_f64(self.reserve_y),
+        );
+
+        // Commit the hook's state, then update fees from strategy
+        self.commit_before_swap(pending);
         self.update_fees(&trade_info);
 
         Some(TradeResult {
@@ -391,9 +1075,151 @@ impl CFMM {
         None
     }
 
+    /// Execute an exact-output swap in arbitrary token direction.
+    ///
+    /// Returns (amount_in, is_buy_from_amm_perspective) on success.
+    pub fn execute_exact_out(
+        &mut self,
+        token_in: usize,
+        token_out: usize,
+        amount_out: f64,
+        timestamp: u64,
+    ) -> Option<(f64, bool)> {
+        if token_in == self.token_a && token_out == self.token_b {
+            let result = self.execute_buy_x_for_y(amount_out, timestamp)?;
+            return Some((result.trade_info.amount_x.to_f64(), true));
+        }
+
+        if token_in == self.token_b && token_out == self.token_a {
+            let result = self.execute_sell_x(amount_out, timestamp)?;
+            return Some((result.trade_info.amount_y.to_f64(), false));
+        }
+
+        None
+    }
+
+    /// Integer quote at `fee`; `x_in` is true when the trader pays X.
+    fn wad_swap(&self, x_in: bool, exact_in: bool, amount: f64, fee: Wad) -> Option<WadSwap> {
+        let pool = self.wad_pool.as_ref()?;
+        let amount = Wad::from_f64(amount);
+        if exact_in {
+            pool.quote_exact_in(x_in, amount, fee, self.fee_mode)
+        } else {
+            pool.quote_exact_out(x_in, amount, fee, self.fee_mode)
+        }
+    }
+
+    /// Integer counterpart of the f64 quotes, in the same (amount, fee) shape.
+    fn quote_wad(&self, x_in: bool, exact_in: bool, amount: f64, fee: Wad) -> (f64, f64) {
+        match self.wad_swap(x_in, exact_in, amount, fee) {
+            Some(swap) if exact_in => (swap.amount_out.to_f64(), swap.fee.to_f64()),
+            Some(swap) => (swap.amount_in.to_f64(), swap.fee.to_f64()),
+            None => (0.0, 0.0),
+        }
+    }
+
+    /// Execute a swap against the integer balances.
+    fn execute_wad(
+        &mut self,
+        x_in: bool,
+        exact_in: bool,
+        amount: f64,
+        fee: Wad,
+        pending: Option<PendingState>,
+        timestamp: u64,
+    ) -> Option<TradeResult> {
+        let swap = self.wad_swap(x_in, exact_in, amount, fee)?;
+        let protocol_fraction = Wad::from_f64(self.protocol_fee_fraction);
+        let fee_mode = self.fee_mode;
+        let pool = self.wad_pool.as_mut()?;
+        pool.apply(x_in, &swap, fee_mode, protocol_fraction);
+        let (reserve_x, reserve_y) = (pool.reserve_x, pool.reserve_y);
+        self.sync_from_wad();
+
+        let (amount_x, amount_y) = if x_in {
+            (swap.amount_in, swap.amount_out)
+        } else {
+            (swap.amount_out, swap.amount_in)
+        };
+        let trade_info = TradeInfo::new(x_in, amount_x, amount_y, timestamp, reserve_x, reserve_y);
+
+        // Commit the hook's state, then update fees from strategy
+        self.commit_before_swap(pending);
+        self.update_fees(&trade_info);
+
+        Some(TradeResult {
+            trade_info,
+            fee_amount: swap.fee.to_f64(),
+        })
+    }
+
+    /// Mirror the integer balances into the f64 fields.
+    fn sync_from_wad(&mut self) {
+        if let Some(pool) = &self.wad_pool {
+            self.reserve_x = pool.reserve_x.to_f64();
+            self.reserve_y = pool.reserve_y.to_f64();
+            self.accumulated_fees_x = pool.fees_x.to_f64();
+            self.accumulated_fees_y = pool.fees_y.to_f64();
+            self.protocol_fees_x = pool.protocol_fees_x.to_f64();
+            self.protocol_fees_y = pool.protocol_fees_y.to_f64();
+        }
+    }
+
+    /// Distribute a collected fee (already removed from the trade flow).
+    ///
+    /// The protocol share goes to the treasury bucket. The LP share goes to
+    /// the separate fee bucket, or back into the reserves when compounding.
+    fn credit_fee(&mut self, fee_amount: f64, in_x: bool) {
+        let protocol_amount = fee_amount * self.protocol_fee_fraction;
+        let lp_amount = fee_amount - protocol_amount;
+        let compounding = self.fee_mode == FeeMode::Compounding;
+        let (reserve, lp_bucket, protocol_bucket) = if in_x {
+            (
+                &mut self.reserve_x,
+                &mut self.accumulated_fees_x,
+                &mut self.protocol_fees_x,
+            )
+        } else {
+            (
+                &mut self.reserve_y,
+                &mut self.accumulated_fees_y,
+                &mut self.protocol_fees_y,
+            )
+        };
+        if compounding {
+            *reserve += lp_amount;
+        } else {
+            *lp_bucket += lp_amount;
+        }
+        *protocol_bucket += protocol_amount;
+    }
+
+    /// Give the strategy its per-step heartbeat and apply the fees it returns.
+    ///
+    /// Runs whether or not the pool traded this step, so strategies can let
+    /// fees decay during quiet periods. A no-op without an `onStep` hook.
+    pub fn on_step(&mut self, timestamp: u64) {
+        if !self.strategy.implements_on_step() {
+            return;
+        }
+        let (reserve_x, reserve_y) = self.wad_reserves();
+        let result = self.strategy.on_step(timestamp, reserve_x, reserve_y);
+        self.apply_fees(result);
+    }
+
+    /// Post the fees a callback returned, or handle its failure.
+    fn apply_fees(&mut self, result: Result<(Wad, Wad), EVMError>) {
+        match result {
+            Ok((bid_fee, ask_fee)) => {
+                self.current_fees = FeeQuote::new(bid_fee.clamp_fee(), ask_fee.clamp_fee());
+            }
+            Err(error) => self.on_failure(error),
+        }
+    }
+
     /// Update fees from strategy after a trade.
     fn update_fees(&mut self, trade_info: &TradeInfo) {
-        if self.use_v2_callbacks {
+        let result = if self.use_v2_callbacks {
             let trade_v2 = TradeInfoV2 {
                 is_buy: trade_info.is_buy,
                 amount_a: trade_info.amount_x,
@@ -405,18 +1231,11 @@ impl CFMM {
                 token_a: self.token_a as u64,
                 token_b: self.token_b as u64,
             };
-
-            if let Ok((bid_fee, ask_fee)) = self.strategy.after_swap_v2(&trade_v2) {
-                self.current_fees = FeeQuote::new(bid_fee.clamp_fee(), ask_fee.clamp_fee());
-            } else if let Ok((bid_fee, ask_fee)) = self.strategy.after_swap(trade_info) {
-                // Keep running even if V2 callback reverts.
-                self.current_fees = FeeQuote::new(bid_fee.clamp_fee(), ask_fee.clamp_fee());
-                self.use_v2_callbacks = false;
-            }
-        } else if let Ok((bid_fee, ask_fee)) = self.strategy.after_swap(trade_info) {
-            self.current_fees = FeeQuote::new(bid_fee.clamp_fee(), ask_fee.clamp_fee());
-        }
-        // On error, keep current fees
+            self.strategy.after_swap_v2(&trade_v2)
+        } else {
+            self.strategy.after_swap(trade_info)
+        };
+        self.apply_fees(result);
     }
 
     /// Reset the AMM for a new simulation.
@@ -429,8 +1248,20 @@ impl CFMM {
         self.reserve_y = reserve_y;
         self.accumulated_fees_x = 0.0;
         self.accumulated_fees_y = 0.0;
+        self.protocol_fees_x = 0.0;
+        self.protocol_fees_y = 0.0;
+        self.total_shares = (reserve_x * reserve_y).sqrt();
+        if self.wad_pool.is_some() {
+            self.wad_pool = Some(WadPool::new(
+                Wad::from_f64(reserve_x),
+                Wad::from_f64(reserve_y),
+            ));
+            self.sync_from_wad();
+        }
         self.initialized = false;
         self.use_v2_callbacks = false;
+        self.failures = FailureCounts::default();
+        self.disqualification = None;
         self.strategy.reset()
     }
 }
@@ -438,7 +1269,9 @@ impl CFMM {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::wad::WAD;
+    use crate::evm::test_support::{
+        BEFORE_SWAP_INIT, REVERT_AFTER_SWAP_INIT, REVERT_BEFORE_SWAP_INIT,
+    };
 
     // Note: Full tests require EVM bytecode, which is complex to embed.
     // The Python integration tests will verify correctness.
@@ -452,7 +1285,7 @@ mod tests {
         let rx = 1000.0;
         let ry = 1000.0;
         let amount_x = 10.0;
-        let fee = 0.0025;
+        let fee = fee_quote.bid_fee.to_f64();
         let gamma = 1.0 - fee;
         let net_x = amount_x * gamma;
         let k = rx * ry;
@@ -463,4 +1296,172 @@ mod tests {
         // y_out should be approximately 9.876 (accounting for fee and price impact)
         assert!(y_out > 9.8 && y_out < 10.0);
     }
+
+    #[test]
+    fn test_fee_mode_parse() {
+        assert_eq!(FeeMode::parse("input").unwrap(), FeeMode::Input);
+        assert_eq!(FeeMode::parse("output").unwrap(), FeeMode::Output);
+        assert_eq!(FeeMode::parse("compounding").unwrap(), FeeMode::Compounding);
+        assert!(FeeMode::parse("v2").is_err());
+        assert!(!FeeMode::Output.fee_on_input());
+    }
+
+    fn reverting_pool(policy: FailurePolicy) -> CFMM {
+        let strategy =
+            EVMStrategy::new(REVERT_AFTER_SWAP_INIT.to_vec(), "reverter".into()).unwrap();
+        let mut amm = CFMM::new(strategy, 1000.0, 1000.0);
+        amm.set_failure_policy(policy);
+        amm.initialize().unwrap();
+        assert_eq!(amm.fees().bid_fee, Wad(42));
+        amm
+    }
+
+    #[test]
+    fn test_failure_policy_parse() {
+        assert_eq!(
+            FailurePolicy::parse("keep", 0.0).unwrap(),
+            FailurePolicy::Keep
+        );
+        assert_eq!(
+            FailurePolicy::parse("default", 0.003).unwrap(),
+            FailurePolicy::DefaultFee(Wad::from_bps(30))
+        );
+        assert_eq!(
+            FailurePolicy::parse("max_fee", 0.0).unwrap(),
+            FailurePolicy::MaxFee
+        );
+        assert_eq!(
+            FailurePolicy::parse("disqualify", 0.0).unwrap(),
+            FailurePolicy::Disqualify
+        );
+        assert!(FailurePolicy::parse("ignore", 0.0).is_err());
+    }
+
+    #[test]
+    fn test_failed_callbacks_are_counted_and_handled() {
+        let mut amm = reverting_pool(FailurePolicy::Keep);
+        assert!(amm.execute_buy_x(1.0, 0).is_some());
+        assert!(amm.execute_sell_x(1.0, 1).is_some());
+        assert_eq!(amm.fees().ask_fee, Wad(42));
+        assert_eq!(amm.failures().reverts, 2);
+        assert!(amm.take_disqualification().is_none());
+
+        let mut amm = reverting_pool(FailurePolicy::MaxFee);
+        amm.execute_buy_x(1.0, 0);
+        assert_eq!(amm.fees().bid_fee, Wad(MAX_FEE));
+
+        let mut amm = reverting_pool(FailurePolicy::Disqualify);
+        amm.execute_buy_x(1.0, 5);
+        amm.execute_buy_x(1.0, 6);
+        match amm.take_disqualification() {
+            Some(EVMError::Reverted(revert)) => {
+                assert_eq!((revert.callback, revert.timestamp), ("afterSwap", Some(5)));
+            }
+            other => panic!("expected a revert, got {:?}", other),
+        }
+        assert!(amm.take_disqualification().is_none());
+
+        amm.reset(1000.0, 1000.0).unwrap();
+        assert_eq!(amm.failures(), FailureCounts::default());
+    }
+
+    fn hooked_pool() -> CFMM {
+        let strategy = EVMStrategy::new(BEFORE_SWAP_INIT.to_vec(), "hooked".into()).unwrap();
+        let mut amm = CFMM::new(strategy, 1000.0, 1000.0);
+        amm.initialize().unwrap();
+        assert!(amm.has_before_swap());
+        assert_eq!(amm.fees().ask_fee, Wad(0));
+        amm
+    }
+
+    /// beforeSwap calls whose state was committed.
+    fn hook_calls(amm: &CFMM) -> f64 {
+        amm.strategy_slots().unwrap()[0]
+    }
+
+    #[test]
+    fn test_before_swap_fee_is_quoted_and_charged() {
+        let mut amm = hooked_pool();
+        assert_eq!(amm.fee_for(true, 10.0), Wad::from_bps(100));
+
+        let (y_out, fee) = amm.quote_buy_x(10.0);
+        assert!((fee - 0.1).abs() < 1e-12);
+        let trade = amm.execute_buy_x(10.0, 1).unwrap();
+        assert_eq!(trade.fee_amount, fee);
+        assert_eq!(trade.trade_info.amount_y, Wad::from_f64(y_out));
+
+        let (y_in, fee) = amm.quote_sell_x(5.0);
+        let trade = amm.execute_sell_x(5.0, 2).unwrap();
+        assert!((fee / y_in - 0.01).abs() < 1e-12);
+        assert_eq!(trade.fee_amount, fee);
+        assert_eq!(trade.trade_info.amount_y, Wad::from_f64(y_in));
+
+        // Quotes leave the strategy untouched; executed trades commit
+        assert_eq!(hook_calls(&amm), 2.0);
+    }
+
+    #[test]
+    fn test_before_swap_state_commits_only_on_execution() {
+        let mut amm = hooked_pool();
+        amm.set_fee_mode(FeeMode::Output);
+        // 995 X fits at the posted 0% fee, but the hook's 1% fee withholds
+        // more X than the pool holds, so the trade fails
+        assert!(amm.execute_sell_x(995.0, 1).is_none());
+        assert_eq!(hook_calls(&amm), 0.0);
+
+        assert!(amm.execute_sell_x(10.0, 2).is_some());
+        assert_eq!(hook_calls(&amm), 1.0);
+    }
+
+    #[test]
+    fn test_before_swap_failure_reports_the_trade_step() {
+        let strategy =
+            EVMStrategy::new(REVERT_BEFORE_SWAP_INIT.to_vec(), "reverter".into()).unwrap();
+        let mut amm = CFMM::new(strategy, 1000.0, 1000.0);
+        amm.set_failure_policy(FailurePolicy::Disqualify);
+        amm.initialize().unwrap();
+        assert!(amm.has_before_swap());
+
+        assert!(amm.execute_buy_x_for_y(1.0, 7).is_some());
+        match amm.take_disqualification() {
+            Some(EVMError::Reverted(revert)) => {
+                assert_eq!((revert.callback, revert.timestamp), ("beforeSwap", Some(7)));
+            }
+            other => panic!("expected a revert, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_before_swap_fee_drives_arbitrage_and_routing() {
+        use crate::evm::test_support::COUNTER_INIT;
+        use crate::market::{Arbitrageur, OrderRouter, RetailOrder};
+
+        // A 0.5% mispricing is inside the hook's 1% fee: no arbitrage
+        let arbitrageur = Arbitrageur::new();
+        let mut amm = hooked_pool();
+        assert!(arbitrageur.execute_arb(&mut amm, 1.005, 1).is_none());
+        assert_eq!(hook_calls(&amm), 0.0);
+        assert!(arbitrageur.execute_arb(&mut amm, 1.05, 2).is_some());
+        assert_eq!(hook_calls(&amm), 1.0);
+        // Arbitrage stops where the price net of the 1% fee meets fair value
+        let (rx, ry) = amm.reserves();
+        assert!((ry / rx - 1.05 * 0.99).abs() < 1e-3);
+
+        // The router sends less of an order to the pool charging more
+        let counter = EVMStrategy::new(COUNTER_INIT.to_vec(), "counter".into()).unwrap();
+        let mut plain = CFMM::new(counter, 1000.0, 1000.0);
+        plain.initialize().unwrap();
+        let mut amms = vec![hooked_pool(), plain];
+        let order = RetailOrder {
+            side: "buy",
+            size: 100.0,
+            exact_out: false,
+        };
+        OrderRouter::new().route_order(&order, &mut amms, 1.0, 3);
+        // Y each pool took in: reserves plus the fees set aside
+        let spent = |amm: &CFMM| amm.reserves().1 + amm.accumulated_fees().1 - 1000.0;
+        assert!(spent(&amms[0]) > 0.0);
+        assert!(spent(&amms[0]) < spent(&amms[1]));
+        assert_eq!(hook_calls(&amms[0]), 1.0);
+    }
 }
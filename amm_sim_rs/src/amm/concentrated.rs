@@ -0,0 +1,373 @@
+//! Concentrated liquidity curve (Uniswap V3-style price ranges).
+//!
+//! Liquidity is split across one or more price ranges [p_a, p_b] with fixed
+//! relative weights. Inside a range with liquidity L, the real reserves are
+//!   x = L * (1/sqrt(p) - 1/sqrt(p_b)),  y = L * (sqrt(p) - sqrt(p_a))
+//! i.e. a constant product on virtual reserves (x + L/sqrt(p_b), y + L*sqrt(p_a)).
+//!
+//! Reserves passed to the curve are real balances. The current price and
+//! liquidity scale are recovered from them, so the curve itself is stateless.
+//! Outside the union of ranges there is no liquidity: the pool holds only one
+//! token and its price stays pinned at the range boundary.
+
+use crate::amm::curve::{CurveKind, PricingCurve};
+
+/// Price ratio between adjacent ticks.
+const TICK_BASE: f64 = 1.0001;
+
+/// Relative slack when checking whether a trade fits in the ranges, so that
+/// trades sized exactly to a range boundary are not rejected by rounding.
+const CAPACITY_TOLERANCE: f64 = 1e-12;
+
+/// A liquidity range with a relative liquidity weight.
+#[derive(Debug, Clone, Copy)]
+struct LiquidityRange {
+    sqrt_lower: f64,
+    sqrt_upper: f64,
+    weight: f64,
+}
+
+/// Concentrated liquidity curve over a set of price ranges.
+#[derive(Debug, Clone)]
+pub struct ConcentratedLiquidity {
+    ranges: Vec<LiquidityRange>,
+    /// Sorted, de-duplicated sqrt prices of all range bounds
+    sqrt_breakpoints: Vec<f64>,
+    /// Liquidity per unit scale between consecutive breakpoints
+    segment_liquidity: Vec<f64>,
+}
+
+impl ConcentratedLiquidity {
+    /// Create a curve from `(price_lower, price_upper, liquidity_weight)` ranges.
+    pub fn new(ranges: &[(f64, f64, f64)]) -> Result<Self, String> {
+        if ranges.is_empty() {
+            return Err("concentrated liquidity requires at least one range".to_string());
+        }
+
+        let mut parsed = Vec::with_capacity(ranges.len());
+        for &(lower, upper, weight) in ranges {
+            if !(lower > 0.0 && upper > lower && upper.is_finite()) {
+                return Err(format!(
+                    "invalid price range [{}, {}]: bounds must satisfy 0 < lower < upper",
+                    lower, upper
+                ));
+            }
+            if !(weight > 0.0 && weight.is_finite()) {
+                return Err(format!(
+                    "range liquidity weight must be positive, got {}",
+                    weight
+                ));
+            }
+            parsed.push(LiquidityRange {
+                sqrt_lower: lower.sqrt(),
+                sqrt_upper: upper.sqrt(),
+                weight,
+            });
+        }
+
+        let mut sqrt_breakpoints: Vec<f64> = parsed
+            .iter()
+            .flat_map(|r| [r.sqrt_lower, r.sqrt_upper])
+            .collect();
+        sqrt_breakpoints.sort_by(|a, b| a.total_cmp(b));
+        sqrt_breakpoints.dedup();
+
+        let segment_liquidity = sqrt_breakpoints
+            .windows(2)
+            .map(|w| {
+                parsed
+                    .iter()
+                    .filter(|r| r.sqrt_lower <= w[0] && r.sqrt_upper >= w[1])
+                    .map(|r| r.weight)
+                    .sum()
+            })
+            .collect();
+
+        Ok(Self {
+            ranges: parsed,
+            sqrt_breakpoints,
+            segment_liquidity,
+        })
+    }
+
+    /// Create a curve from `(tick_lower, tick_upper, liquidity_weight)` ranges,
+    /// where price = 1.0001^tick.
+    pub fn from_ticks(ranges: &[(i32, i32, f64)]) -> Result<Self, String> {
+        let priced: Vec<(f64, f64, f64)> = ranges
+            .iter()
+            .map(|&(lower, upper, weight)| (TICK_BASE.powi(lower), TICK_BASE.powi(upper), weight))
+            .collect();
+        Self::new(&priced)
+    }
+
+    /// Lowest and highest price with liquidity.
+    pub fn price_bounds(&self) -> (f64, f64) {
+        let lo = self.sqrt_breakpoints[0];
+        let hi = self.sqrt_breakpoints[self.sqrt_breakpoints.len() - 1];
+        (lo * lo, hi * hi)
+    }
+
+    /// Real X per unit liquidity scale at sqrt price `sp`.
+    fn unit_x(&self, sp: f64) -> f64 {
+        self.ranges
+            .iter()
+            .map(|r| r.weight * (1.0 / sp.clamp(r.sqrt_lower, r.sqrt_upper) - 1.0 / r.sqrt_upper))
+            .sum()
+    }
+
+    /// Real Y per unit liquidity scale at sqrt price `sp`.
+    fn unit_y(&self, sp: f64) -> f64 {
+        self.ranges
+            .iter()
+            .map(|r| r.weight * (sp.clamp(r.sqrt_lower, r.sqrt_upper) - r.sqrt_lower))
+            .sum()
+    }
+
+    /// Recover (sqrt price, liquidity scale) from real reserves.
+    ///
+    /// y/x increases monotonically with price. Inside a segment with unit
+    /// liquidity l, y/x = r reduces to l*u^2 + (A - r*C)*u - r*l = 0 in u = sqrt(p),
+    /// where A = y(b_j) - l*sqrt(b_j) and C = x(b_j+1) - l/sqrt(b_j+1).
+    fn state(&self, reserve_x: f64, reserve_y: f64) -> (f64, f64) {
+        let n = self.sqrt_breakpoints.len();
+        let lowest = self.sqrt_breakpoints[0];
+        let highest = self.sqrt_breakpoints[n - 1];
+        if reserve_x <= 0.0 {
+            return (highest, reserve_y / self.unit_y(highest));
+        }
+        if reserve_y <= 0.0 {
+            return (lowest, reserve_x / self.unit_x(lowest));
+        }
+
+        let ratio = reserve_y / reserve_x;
+        let mut sp = highest;
+        for j in 0..n - 1 {
+            let (b_lo, b_hi) = (self.sqrt_breakpoints[j], self.sqrt_breakpoints[j + 1]);
+            let (x_hi, y_hi) = (self.unit_x(b_hi), self.unit_y(b_hi));
+            if x_hi > 0.0 && y_hi / x_hi < ratio {
+                continue;
+            }
+            let l = self.segment_liquidity[j];
+            if l == 0.0 {
+                sp = b_lo;
+                break;
+            }
+            let a = self.unit_y(b_lo) - l * b_lo;
+            let c = x_hi - l / b_hi;
+            let b = a - ratio * c;
+            let u = (-b + (b * b + 4.0 * l * l * ratio).sqrt()) / (2.0 * l);
+            sp = u.clamp(b_lo, b_hi);
+            break;
+        }
+
+        let (ux, uy) = (self.unit_x(sp), self.unit_y(sp));
+        let scale = if reserve_x * ux >= reserve_y * uy / (sp * sp) {
+            reserve_x / ux
+        } else {
+            reserve_y / uy
+        };
+        (sp, scale)
+    }
+
+    /// Sqrt price at which unit X reserves equal `target`, or None if the
+    /// ranges cannot absorb that much X.
+    fn sqrt_price_at_unit_x(&self, target: f64) -> Option<f64> {
+        let n = self.sqrt_breakpoints.len();
+        let capacity = self.unit_x(self.sqrt_breakpoints[0]);
+        if target > capacity * (1.0 + CAPACITY_TOLERANCE) {
+            return None;
+        }
+        let target = target.min(capacity);
+        for j in (0..n - 1).rev() {
+            let (b_lo, b_hi) = (self.sqrt_breakpoints[j], self.sqrt_breakpoints[j + 1]);
+            let x_hi = self.unit_x(b_hi);
+            if target < x_hi || target > self.unit_x(b_lo) {
+                continue;
+            }
+            let l = self.segment_liquidity[j];
+            if l == 0.0 {
+                return Some(b_lo);
+            }
+            let inv = (target - x_hi) / l + 1.0 / b_hi;
+            return Some((1.0 / inv).clamp(b_lo, b_hi));
+        }
+        Some(self.sqrt_breakpoints[n - 1])
+    }
+
+    /// Sqrt price at which unit Y reserves equal `target`, or None if the
+    /// ranges cannot absorb that much Y.
+    fn sqrt_price_at_unit_y(&self, target: f64) -> Option<f64> {
+        let n = self.sqrt_breakpoints.len();
+        let capacity = self.unit_y(self.sqrt_breakpoints[n - 1]);
+        if target > capacity * (1.0 + CAPACITY_TOLERANCE) {
+            return None;
+        }
+        let target = target.min(capacity);
+        for j in 0..n - 1 {
+            let (b_lo, b_hi) = (self.sqrt_breakpoints[j], self.sqrt_breakpoints[j + 1]);
+            let y_lo = self.unit_y(b_lo);
+            if target < y_lo || target > self.unit_y(b_hi) {
+                continue;
+            }
+            let l = self.segment_liquidity[j];
+            if l == 0.0 {
+                return Some(b_hi);
+            }
+            return Some(((target - y_lo) / l + b_lo).clamp(b_lo, b_hi));
+        }
+        Some(self.sqrt_breakpoints[0])
+    }
+}
+
+impl PricingCurve for ConcentratedLiquidity {
+    fn kind(&self) -> CurveKind {
+        CurveKind::Concentrated
+    }
+
+    /// Liquidity scale (L per unit range weight).
+    fn invariant(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        self.state(reserve_x, reserve_y).1
+    }
+
+    fn spot_price(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        let sp = self.state(reserve_x, reserve_y).0;
+        sp * sp
+    }
+
+    /// Returns 0 if the input would push the price below the lowest range.
+    fn y_out_for_x_in(&self, reserve_x: f64, reserve_y: f64, x_in: f64) -> f64 {
+        let (_, scale) = self.state(reserve_x, reserve_y);
+        match self.sqrt_price_at_unit_x((reserve_x + x_in) / scale) {
+            Some(sp) => reserve_y - scale * self.unit_y(sp),
+            None => 0.0,
+        }
+    }
+
+    /// Returns 0 if the input would push the price above the highest range.
+    fn x_out_for_y_in(&self, reserve_x: f64, reserve_y: f64, y_in: f64) -> f64 {
+        let (_, scale) = self.state(reserve_x, reserve_y);
+        match self.sqrt_price_at_unit_y((reserve_y + y_in) / scale) {
+            Some(sp) => reserve_x - scale * self.unit_x(sp),
+            None => 0.0,
+        }
+    }
+
+    fn y_in_for_x_out(&self, reserve_x: f64, reserve_y: f64, x_out: f64) -> f64 {
+        if x_out >= reserve_x {
+            return f64::INFINITY;
+        }
+        let (_, scale) = self.state(reserve_x, reserve_y);
+        match self.sqrt_price_at_unit_x((reserve_x - x_out) / scale) {
+            Some(sp) => scale * self.unit_y(sp) - reserve_y,
+            None => f64::INFINITY,
+        }
+    }
+
+    fn x_in_for_y_out(&self, reserve_x: f64, reserve_y: f64, y_out: f64) -> f64 {
+        if y_out >= reserve_y {
+            return f64::INFINITY;
+        }
+        let (_, scale) = self.state(reserve_x, reserve_y);
+        match self.sqrt_price_at_unit_y((reserve_y - y_out) / scale) {
+            Some(sp) => scale * self.unit_x(sp) - reserve_x,
+            None => f64::INFINITY,
+        }
+    }
+
+    /// Prices outside the ranges map to the nearest boundary, where the pool
+    /// holds only one token.
+    fn reserve_x_at_price(&self, reserve_x: f64, reserve_y: f64, price: f64) -> f64 {
+        let (_, scale) = self.state(reserve_x, reserve_y);
+        let n = self.sqrt_breakpoints.len();
+        let sp = price
+            .sqrt()
+            .clamp(self.sqrt_breakpoints[0], self.sqrt_breakpoints[n - 1]);
+        scale * self.unit_x(sp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reserves_at(curve: &ConcentratedLiquidity, price: f64, scale: f64) -> (f64, f64) {
+        let sp = price.sqrt();
+        (scale * curve.unit_x(sp), scale * curve.unit_y(sp))
+    }
+
+    #[test]
+    fn test_rejects_invalid_ranges() {
+        assert!(ConcentratedLiquidity::new(&[]).is_err());
+        assert!(ConcentratedLiquidity::new(&[(2.0, 1.0, 1.0)]).is_err());
+        assert!(ConcentratedLiquidity::new(&[(0.0, 1.0, 1.0)]).is_err());
+        assert!(ConcentratedLiquidity::new(&[(1.0, 2.0, 0.0)]).is_err());
+    }
+
+    #[test]
+    fn test_state_recovers_price_and_liquidity() {
+        let curve = ConcentratedLiquidity::new(&[
+            (80.0, 125.0, 1.0),
+            (95.0, 105.0, 3.0),
+            (130.0, 150.0, 1.0),
+        ])
+        .unwrap();
+        for price in [85.0, 97.0, 100.0, 104.0, 120.0, 140.0] {
+            let (rx, ry) = reserves_at(&curve, price, 500.0);
+            assert!((curve.spot_price(rx, ry) - price).abs() / price < 1e-9);
+            assert!((curve.invariant(rx, ry) - 500.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_single_range_matches_virtual_constant_product() {
+        let (pa, pb): (f64, f64) = (80.0, 125.0);
+        let curve = ConcentratedLiquidity::new(&[(pa, pb, 1.0)]).unwrap();
+        let (rx, ry) = reserves_at(&curve, 100.0, 1000.0);
+
+        // Virtual reserves follow x * y = L^2
+        let vx = rx + 1000.0 / pb.sqrt();
+        let vy = ry + 1000.0 * pa.sqrt();
+        let y_out = curve.y_out_for_x_in(rx, ry, 1.0);
+        let cp_out = vy - vx * vy / (vx + 1.0);
+        assert!((y_out - cp_out).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_trades_round_trip_across_ranges() {
+        let curve = ConcentratedLiquidity::new(&[(80.0, 125.0, 1.0), (95.0, 105.0, 3.0)]).unwrap();
+        let (rx, ry) = reserves_at(&curve, 100.0, 1000.0);
+
+        let y_out = curve.y_out_for_x_in(rx, ry, 15.0);
+        let x_in = curve.x_in_for_y_out(rx, ry, y_out);
+        assert!((x_in - 15.0).abs() < 1e-6);
+        assert!(curve.spot_price(rx + 15.0, ry - y_out) < 95.0);
+
+        let x_out = curve.x_out_for_y_in(rx, ry, 500.0);
+        let y_in = curve.y_in_for_x_out(rx, ry, x_out);
+        assert!((y_in - 500.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_range_exit() {
+        let curve = ConcentratedLiquidity::new(&[(80.0, 125.0, 1.0)]).unwrap();
+        let (rx, ry) = reserves_at(&curve, 100.0, 1000.0);
+
+        // Price above the range: all X is sold and the price is pinned at the top.
+        assert_eq!(curve.reserve_x_at_price(rx, ry, 200.0), 0.0);
+        let y_in = curve.y_in_for_x_out(rx, ry, rx * (1.0 - 1e-12));
+        assert!((curve.spot_price(rx * 1e-12, ry + y_in) - 125.0).abs() < 1e-6);
+
+        // Selling more X than the range can absorb cannot be filled.
+        let (x_max, _) = reserves_at(&curve, 80.0, 1000.0);
+        assert_eq!(curve.y_out_for_x_in(rx, ry, x_max - rx + 1.0), 0.0);
+        assert!(curve.y_out_for_x_in(rx, ry, x_max - rx - 1.0) > 0.0);
+    }
+
+    #[test]
+    fn test_from_ticks() {
+        let curve = ConcentratedLiquidity::from_ticks(&[(-100, 100, 1.0)]).unwrap();
+        let (lo, hi) = curve.price_bounds();
+        assert!((lo - 1.0001_f64.powi(-100)).abs() < 1e-12);
+        assert!((hi - 1.0001_f64.powi(100)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,228 @@
+//! Pricing curves (trading invariants) for two-asset pools.
+//!
+//! A curve describes the fee-free trading function of a pool. The pool
+//! (`CFMM`) takes care of fees, fee buckets and strategy callbacks, and
+//! delegates all invariant math to its curve. Amounts passed to and
+//! returned from curve methods are always net of fees.
+
+use crate::amm::concentrated::ConcentratedLiquidity;
+use crate::amm::pmm::ProactiveMarketMaker;
+use crate::amm::stableswap::StableSwap;
+use crate::amm::weighted::WeightedProduct;
+
+/// Identifies the curve family, so that market actors can pick
+/// closed-form solutions when they exist for a given pool pair.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurveKind {
+    /// x * y = k
+    ConstantProduct,
+    /// x^w * y^(1-w) = k, where `weight` is w
+    Weighted { weight: f64 },
+    /// Curve StableSwap with amplification coefficient A
+    StableSwap { amplification: f64 },
+    /// Constant product on virtual reserves within price ranges
+    Concentrated,
+    /// DODO PMM centred on an oracle price, with slippage parameter k
+    Pmm { k: f64 },
+    /// Any other curve; handled numerically by market actors.
+    Custom,
+}
+
+/// Trading invariant of a two-asset pool.
+///
+/// Prices are always quoted as Y per X. Methods take the current reserves
+/// so that curves stay stateless and can be shared between pools.
+pub trait PricingCurve: Send + Sync {
+    /// Curve family.
+    fn kind(&self) -> CurveKind {
+        CurveKind::Custom
+    }
+
+    /// Value of the invariant at the given reserves.
+    fn invariant(&self, reserve_x: f64, reserve_y: f64) -> f64;
+
+    /// Marginal price (Y per X) at the given reserves.
+    fn spot_price(&self, reserve_x: f64, reserve_y: f64) -> f64;
+
+    /// Y received for depositing `x_in` (net of fees).
+    fn y_out_for_x_in(&self, reserve_x: f64, reserve_y: f64, x_in: f64) -> f64;
+
+    /// X received for depositing `y_in` (net of fees).
+    fn x_out_for_y_in(&self, reserve_x: f64, reserve_y: f64, y_in: f64) -> f64;
+
+    /// Y that must be deposited (net of fees) to withdraw `x_out`.
+    ///
+    /// Returns `f64::INFINITY` if `x_out` cannot be withdrawn.
+    fn y_in_for_x_out(&self, reserve_x: f64, reserve_y: f64, x_out: f64) -> f64;
+
+    /// X that must be deposited (net of fees) to withdraw `y_out`.
+    ///
+    /// Returns `f64::INFINITY` if `y_out` cannot be withdrawn.
+    fn x_in_for_y_out(&self, reserve_x: f64, reserve_y: f64, y_out: f64) -> f64;
+
+    /// X reserve at which the marginal price equals `price`, moving along
+    /// the invariant through the current reserves.
+    fn reserve_x_at_price(&self, reserve_x: f64, reserve_y: f64, price: f64) -> f64;
+
+    /// Update the external reference price, for curves anchored to an oracle.
+    ///
+    /// Called once per step before any trading. No-op by default.
+    fn set_oracle_price(&mut self, _price: f64) {}
+
+    /// Scale any reserve-denominated parameters by `factor` after liquidity
+    /// is added or removed pro rata.
+    ///
+    /// No-op by default: most curves are homogeneous in the reserves.
+    fn rescale_liquidity(&mut self, _factor: f64) {}
+}
+
+/// Curve selection shared by the simulation configs.
+///
+/// Leaving every field unset selects the constant product curve.
+/// At most one curve family may be configured.
+#[derive(Debug, Clone, Default)]
+pub struct CurveParams {
+    /// Weight of X for a weighted product pool
+    pub weight: Option<f64>,
+    /// Amplification coefficient for a StableSwap pool
+    pub amplification: Option<f64>,
+    /// `(price_lower, price_upper, liquidity_weight)` ranges for a
+    /// concentrated liquidity pool; empty for full-range curves
+    pub ranges: Vec<(f64, f64, f64)>,
+    /// Slippage parameter k for an oracle-anchored PMM pool
+    pub pmm_k: Option<f64>,
+}
+
+impl CurveParams {
+    /// Build the configured curve for a pool with the given initial reserves.
+    ///
+    /// StableSwap pools are pegged at the initial spot price y / x.
+    /// Concentrated liquidity pools treat the initial reserves as real
+    /// balances, so their starting price is the one implied by the ranges.
+    /// PMM pools target the initial X reserve, with the oracle starting at y / x.
+    pub fn build(&self, initial_x: f64, initial_y: f64) -> Result<Box<dyn PricingCurve>, String> {
+        let configured = [
+            self.weight.is_some(),
+            self.amplification.is_some(),
+            !self.ranges.is_empty(),
+            self.pmm_k.is_some(),
+        ];
+        if configured.iter().filter(|&&c| c).count() > 1 {
+            return Err(
+                "pool weight, amplification, liquidity ranges and PMM k are mutually exclusive"
+                    .to_string(),
+            );
+        }
+
+        if let Some(weight) = self.weight {
+            Ok(Box::new(WeightedProduct::new(weight)?))
+        } else if let Some(amplification) = self.amplification {
+            Ok(Box::new(StableSwap::new(
+                amplification,
+                initial_y / initial_x,
+            )?))
+        } else if !self.ranges.is_empty() {
+            Ok(Box::new(ConcentratedLiquidity::new(&self.ranges)?))
+        } else if let Some(k) = self.pmm_k {
+            Ok(Box::new(ProactiveMarketMaker::new(
+                k,
+                initial_x,
+                initial_y / initial_x,
+            )?))
+        } else {
+            Ok(Box::new(ConstantProduct))
+        }
+    }
+}
+
+/// Constant product curve (x * y = k).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConstantProduct;
+
+impl PricingCurve for ConstantProduct {
+    fn kind(&self) -> CurveKind {
+        CurveKind::ConstantProduct
+    }
+
+    #[inline]
+    fn invariant(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        reserve_x * reserve_y
+    }
+
+    #[inline]
+    fn spot_price(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        if reserve_x == 0.0 {
+            return 0.0;
+        }
+        reserve_y / reserve_x
+    }
+
+    #[inline]
+    fn y_out_for_x_in(&self, reserve_x: f64, reserve_y: f64, x_in: f64) -> f64 {
+        let k = reserve_x * reserve_y;
+        let new_rx = reserve_x + x_in;
+        reserve_y - k / new_rx
+    }
+
+    #[inline]
+    fn x_out_for_y_in(&self, reserve_x: f64, reserve_y: f64, y_in: f64) -> f64 {
+        let k = reserve_x * reserve_y;
+        let new_ry = reserve_y + y_in;
+        reserve_x - k / new_ry
+    }
+
+    #[inline]
+    fn y_in_for_x_out(&self, reserve_x: f64, reserve_y: f64, x_out: f64) -> f64 {
+        if x_out >= reserve_x {
+            return f64::INFINITY;
+        }
+        let k = reserve_x * reserve_y;
+        let new_rx = reserve_x - x_out;
+        k / new_rx - reserve_y
+    }
+
+    #[inline]
+    fn x_in_for_y_out(&self, reserve_x: f64, reserve_y: f64, y_out: f64) -> f64 {
+        if y_out >= reserve_y {
+            return f64::INFINITY;
+        }
+        let k = reserve_x * reserve_y;
+        let new_ry = reserve_y - y_out;
+        k / new_ry - reserve_x
+    }
+
+    /// x = sqrt(k / p)
+    #[inline]
+    fn reserve_x_at_price(&self, reserve_x: f64, reserve_y: f64, price: f64) -> f64 {
+        (reserve_x * reserve_y / price).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_product_round_trip() {
+        let curve = ConstantProduct;
+        let (rx, ry) = (1000.0, 2000.0);
+
+        let y_out = curve.y_out_for_x_in(rx, ry, 10.0);
+        let x_in = curve.x_in_for_y_out(rx, ry, y_out);
+        assert!((x_in - 10.0).abs() < 1e-9);
+
+        let x_out = curve.x_out_for_y_in(rx, ry, 50.0);
+        let y_in = curve.y_in_for_x_out(rx, ry, x_out);
+        assert!((y_in - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_constant_product_reserve_at_price() {
+        let curve = ConstantProduct;
+        let (rx, ry) = (1000.0, 1000.0);
+
+        let x = curve.reserve_x_at_price(rx, ry, 4.0);
+        let y = curve.invariant(rx, ry) / x;
+        assert!((curve.spot_price(x, y) - 4.0).abs() < 1e-9);
+    }
+}
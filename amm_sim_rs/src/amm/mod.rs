@@ -1,5 +1,17 @@
 //! AMM (Automated Market Maker) module.
 
 pub mod cfmm;
+pub mod concentrated;
+pub mod curve;
+pub mod pmm;
+pub mod stableswap;
+pub mod wad_pool;
+pub mod weighted;
 
-pub use cfmm::CFMM;
+pub use cfmm::{FailurePolicy, FeeMode, LiquidityChange, CFMM};
+pub use concentrated::ConcentratedLiquidity;
+pub use curve::{ConstantProduct, CurveKind, CurveParams, PricingCurve};
+pub use pmm::ProactiveMarketMaker;
+pub use stableswap::StableSwap;
+pub use wad_pool::{WadPool, WadSwap};
+pub use weighted::WeightedProduct;
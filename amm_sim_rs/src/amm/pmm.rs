@@ -0,0 +1,315 @@
+//! Proactive market maker curve (DODO PMM), centred on an oracle price.
+//!
+//! With oracle price i, slippage parameter k, base target B0 and quote
+//! target Q0, the pool trades on one of two branches:
+//! - base short (B <= B0):  Q - Q0 = i * (B0 - B) * (1 - k + k * B0 / B)
+//! - quote short (Q <= Q0): B - B0 = (Q0 - Q) / i * (1 - k + k * Q0 / Q)
+//!
+//! The marginal price equals i at the targets. k = 1 behaves like a constant
+//! product curve around the targets; smaller k concentrates liquidity at i.
+//!
+//! The base target is fixed at pool creation. The quote target is recovered
+//! from the current reserves and oracle price, so moving the oracle re-centres
+//! the curve while trades move along it.
+
+use crate::amm::curve::{CurveKind, PricingCurve};
+
+/// Relative slack when checking whether a trade exhausts the quote reserve.
+const CAPACITY_TOLERANCE: f64 = 1e-12;
+
+/// Oracle-anchored PMM curve.
+#[derive(Debug, Clone, Copy)]
+pub struct ProactiveMarketMaker {
+    /// Slippage parameter k in (0, 1]
+    k: f64,
+    /// Base (X) target B0
+    base_target: f64,
+    /// Oracle price i (Y per X)
+    oracle_price: f64,
+}
+
+impl ProactiveMarketMaker {
+    /// Create a PMM curve with base target `base_target`, centred on `oracle_price`.
+    pub fn new(k: f64, base_target: f64, oracle_price: f64) -> Result<Self, String> {
+        if !(k > 0.0 && k <= 1.0) {
+            return Err(format!(
+                "PMM slippage parameter k must be in (0, 1], got {}",
+                k
+            ));
+        }
+        if !(base_target > 0.0 && base_target.is_finite()) {
+            return Err(format!(
+                "PMM base target must be positive, got {}",
+                base_target
+            ));
+        }
+        if !(oracle_price > 0.0 && oracle_price.is_finite()) {
+            return Err(format!(
+                "oracle price must be positive, got {}",
+                oracle_price
+            ));
+        }
+        Ok(Self {
+            k,
+            base_target,
+            oracle_price,
+        })
+    }
+
+    /// Slippage parameter k.
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    /// Base (X) target B0.
+    pub fn base_target(&self) -> f64 {
+        self.base_target
+    }
+
+    /// Current oracle price (Y per X).
+    pub fn oracle_price(&self) -> f64 {
+        self.oracle_price
+    }
+
+    /// Surplus needed to hold deficit balance `balance` below `target`,
+    /// at `price` surplus tokens per deficit token.
+    #[inline]
+    fn surplus_for_deficit(&self, target: f64, balance: f64, price: f64) -> f64 {
+        price * (target - balance) * (1.0 - self.k + self.k * target / balance)
+    }
+
+    /// Deficit balance that a surplus of `excess` over target corresponds to.
+    ///
+    /// Solves (t - a)(1 - k + k t / a) = excess / price for a, in the
+    /// cancellation-free form a = 2 k t^2 / (b + sqrt(b^2 + 4 k (1 - k) t^2)).
+    #[inline]
+    fn deficit_for_surplus(&self, target: f64, excess: f64, price: f64) -> f64 {
+        let k = self.k;
+        let b = excess / price - (1.0 - 2.0 * k) * target;
+        let disc = b * b + 4.0 * k * (1.0 - k) * target * target;
+        2.0 * k * target * target / (b + disc.sqrt())
+    }
+
+    /// Quote target Q0 implied by the reserves at the current oracle price.
+    ///
+    /// When the pool is short of base this may be negative: the quote
+    /// reserve then runs out before the base target is reached.
+    fn quote_target(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        let i = self.oracle_price;
+        if reserve_x <= self.base_target {
+            return reserve_y - self.surplus_for_deficit(self.base_target, reserve_x, i);
+        }
+        if reserve_y <= 0.0 {
+            return 0.0;
+        }
+        // Quote short: (Q0 - Q)(1 - k + k Q0 / Q) = i (B - B0), quadratic in Q0
+        let k = self.k;
+        let excess = i * (reserve_x - self.base_target);
+        let a = k / reserve_y;
+        let b = 1.0 - 2.0 * k;
+        let c = -((1.0 - k) * reserve_y + excess);
+        (-b + (b * b - 4.0 * a * c).sqrt()) / (2.0 * a)
+    }
+
+    /// Quote reserve on the curve through (B0, Q0) at base reserve `reserve_x`.
+    fn y_at_x(&self, quote_target: f64, reserve_x: f64) -> f64 {
+        let i = self.oracle_price;
+        if reserve_x <= self.base_target {
+            quote_target + self.surplus_for_deficit(self.base_target, reserve_x, i)
+        } else {
+            self.deficit_for_surplus(quote_target, reserve_x - self.base_target, 1.0 / i)
+        }
+    }
+
+    /// Base reserve on the curve through (B0, Q0) at quote reserve `reserve_y`.
+    fn x_at_y(&self, quote_target: f64, reserve_y: f64) -> f64 {
+        let i = self.oracle_price;
+        if reserve_y <= quote_target {
+            self.base_target + self.surplus_for_deficit(quote_target, reserve_y, 1.0 / i)
+        } else {
+            self.deficit_for_surplus(self.base_target, reserve_y - quote_target, i)
+        }
+    }
+}
+
+impl PricingCurve for ProactiveMarketMaker {
+    fn kind(&self) -> CurveKind {
+        CurveKind::Pmm { k: self.k }
+    }
+
+    /// Quote target Q0, constant along trades at a fixed oracle price.
+    fn invariant(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        self.quote_target(reserve_x, reserve_y)
+    }
+
+    /// Base short: i * (1 - k + k (B0 / B)^2); quote short: i / (1 - k + k (Q0 / Q)^2)
+    fn spot_price(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        let (i, k) = (self.oracle_price, self.k);
+        if reserve_x <= self.base_target {
+            if reserve_x <= 0.0 {
+                return f64::INFINITY;
+            }
+            let r = self.base_target / reserve_x;
+            return i * (1.0 - k + k * r * r);
+        }
+        if reserve_y <= 0.0 {
+            return 0.0;
+        }
+        let r = self.quote_target(reserve_x, reserve_y) / reserve_y;
+        i / (1.0 - k + k * r * r)
+    }
+
+    /// Returns 0 if the input would exhaust the quote reserve.
+    fn y_out_for_x_in(&self, reserve_x: f64, reserve_y: f64, x_in: f64) -> f64 {
+        let q0 = self.quote_target(reserve_x, reserve_y);
+        let new_y = self.y_at_x(q0, reserve_x + x_in);
+        if new_y < -CAPACITY_TOLERANCE * reserve_y {
+            return 0.0;
+        }
+        reserve_y - new_y.max(0.0)
+    }
+
+    fn x_out_for_y_in(&self, reserve_x: f64, reserve_y: f64, y_in: f64) -> f64 {
+        let q0 = self.quote_target(reserve_x, reserve_y);
+        reserve_x - self.x_at_y(q0, reserve_y + y_in)
+    }
+
+    fn y_in_for_x_out(&self, reserve_x: f64, reserve_y: f64, x_out: f64) -> f64 {
+        if x_out >= reserve_x {
+            return f64::INFINITY;
+        }
+        let q0 = self.quote_target(reserve_x, reserve_y);
+        self.y_at_x(q0, reserve_x - x_out) - reserve_y
+    }
+
+    fn x_in_for_y_out(&self, reserve_x: f64, reserve_y: f64, y_out: f64) -> f64 {
+        if y_out >= reserve_y {
+            return f64::INFINITY;
+        }
+        let q0 = self.quote_target(reserve_x, reserve_y);
+        self.x_at_y(q0, reserve_y - y_out) - reserve_x
+    }
+
+    /// Inverts the marginal price on the branch that contains `price`.
+    /// If the quote reserve runs out first, returns the base reserve at
+    /// which it does.
+    fn reserve_x_at_price(&self, reserve_x: f64, reserve_y: f64, price: f64) -> f64 {
+        let (i, k) = (self.oracle_price, self.k);
+        let q0 = self.quote_target(reserve_x, reserve_y);
+        let x_exhausted = if q0 < 0.0 {
+            self.deficit_for_surplus(self.base_target, -q0, i)
+        } else {
+            f64::INFINITY
+        };
+
+        let new_x = if price >= i || q0 <= 0.0 {
+            // Base short: B0 / B = sqrt((p / i - 1 + k) / k)
+            let ratio = ((price / i - 1.0 + k) / k).max(0.0).sqrt();
+            self.base_target / ratio
+        } else {
+            // Quote short: Q0 / Q = sqrt((i / p - 1 + k) / k)
+            let ratio = ((i / price - 1.0 + k) / k).sqrt();
+            self.x_at_y(q0, q0 / ratio)
+        };
+        new_x.min(x_exhausted)
+    }
+
+    fn set_oracle_price(&mut self, price: f64) {
+        if price > 0.0 && price.is_finite() {
+            self.oracle_price = price;
+        }
+    }
+
+    /// The base target scales with the pool so the curve keeps its shape.
+    fn rescale_liquidity(&mut self, factor: f64) {
+        if factor > 0.0 && factor.is_finite() {
+            self.base_target *= factor;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm::curve::ConstantProduct;
+
+    #[test]
+    fn test_rejects_invalid_params() {
+        assert!(ProactiveMarketMaker::new(0.0, 100.0, 1.0).is_err());
+        assert!(ProactiveMarketMaker::new(1.5, 100.0, 1.0).is_err());
+        assert!(ProactiveMarketMaker::new(0.5, 0.0, 1.0).is_err());
+        assert!(ProactiveMarketMaker::new(0.5, 100.0, -1.0).is_err());
+    }
+
+    #[test]
+    fn test_prices_at_oracle_on_target() {
+        let curve = ProactiveMarketMaker::new(0.1, 100.0, 100.0).unwrap();
+        assert!((curve.spot_price(100.0, 10_000.0) - 100.0).abs() < 1e-9);
+        assert!((curve.invariant(100.0, 10_000.0) - 10_000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lower_k_means_less_slippage() {
+        let tight = ProactiveMarketMaker::new(0.05, 100.0, 100.0).unwrap();
+        let cp = ConstantProduct;
+        let tight_out = tight.y_out_for_x_in(100.0, 10_000.0, 5.0);
+        let cp_out = cp.y_out_for_x_in(100.0, 10_000.0, 5.0);
+        assert!(tight_out > cp_out);
+        assert!(tight_out < 500.0);
+    }
+
+    #[test]
+    fn test_trades_round_trip_across_target() {
+        let curve = ProactiveMarketMaker::new(0.3, 100.0, 100.0).unwrap();
+        let (rx, ry) = (90.0, 11_200.0);
+        let q0 = curve.invariant(rx, ry);
+
+        // Sell enough X to cross into the quote-short branch and back
+        let y_out = curve.y_out_for_x_in(rx, ry, 25.0);
+        assert!((curve.invariant(rx + 25.0, ry - y_out) - q0).abs() / q0 < 1e-9);
+        let x_in = curve.x_in_for_y_out(rx, ry, y_out);
+        assert!((x_in - 25.0).abs() < 1e-8);
+
+        let x_out = curve.x_out_for_y_in(rx, ry, 800.0);
+        let y_in = curve.y_in_for_x_out(rx, ry, x_out);
+        assert!((y_in - 800.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_reserve_at_price_hits_target() {
+        let curve = ProactiveMarketMaker::new(0.3, 100.0, 100.0).unwrap();
+        let (rx, ry) = (100.0, 10_000.0);
+
+        for target in [80.0, 99.0, 101.0, 130.0] {
+            let new_x = curve.reserve_x_at_price(rx, ry, target);
+            let new_y = curve.y_at_x(curve.invariant(rx, ry), new_x);
+            let spot = curve.spot_price(new_x, new_y);
+            assert!(
+                (spot - target).abs() / target < 1e-9,
+                "target {}: {}",
+                target,
+                spot
+            );
+        }
+    }
+
+    #[test]
+    fn test_oracle_moves_recentre_curve() {
+        let mut curve = ProactiveMarketMaker::new(0.2, 100.0, 100.0).unwrap();
+        curve.set_oracle_price(120.0);
+        // At the base target the pool quotes the oracle price directly.
+        assert!((curve.spot_price(100.0, 10_000.0) - 120.0).abs() < 1e-9);
+        let new_x = curve.reserve_x_at_price(100.0, 10_000.0, 120.0);
+        assert!((new_x - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rescale_keeps_spot_price() {
+        let mut curve = ProactiveMarketMaker::new(0.2, 100.0, 100.0).unwrap();
+        let (rx, ry) = (90.0, 11_100.0);
+        let before = curve.spot_price(rx, ry);
+        curve.rescale_liquidity(1.5);
+        let after = curve.spot_price(rx * 1.5, ry * 1.5);
+        assert!((before - after).abs() / before < 1e-12);
+    }
+}
@@ -0,0 +1,257 @@
+//! StableSwap curve (Curve Finance, two coins).
+//!
+//! Invariant with amplification A and n = 2:
+//!   4A(x + y) + D = 4AD + D^3 / (4xy)
+//!
+//! X balances are scaled by a peg rate before entering the invariant, so the
+//! curve is flattest around spot price = rate (Y per X). Quotes solve the
+//! invariant with Newton's method in f64.
+
+use crate::amm::curve::{CurveKind, PricingCurve};
+
+/// Maximum Newton iterations for D and balance solves.
+const NEWTON_ITERATIONS: usize = 255;
+
+/// Bisection iterations for solving reserves at a target price.
+const PRICE_ITERATIONS: usize = 200;
+
+/// Relative convergence tolerance for Newton iterations.
+const NEWTON_TOLERANCE: f64 = 1e-15;
+
+/// Two-coin StableSwap curve.
+#[derive(Debug, Clone, Copy)]
+pub struct StableSwap {
+    /// Amplification coefficient A
+    amplification: f64,
+    /// Peg price (Y per X); X balances are multiplied by this rate
+    rate: f64,
+}
+
+impl StableSwap {
+    /// Create a StableSwap curve pegged at `rate` (Y per X).
+    pub fn new(amplification: f64, rate: f64) -> Result<Self, String> {
+        if !(amplification > 0.0 && amplification.is_finite()) {
+            return Err(format!(
+                "amplification must be positive, got {}",
+                amplification
+            ));
+        }
+        if !(rate > 0.0 && rate.is_finite()) {
+            return Err(format!("peg rate must be positive, got {}", rate));
+        }
+        Ok(Self {
+            amplification,
+            rate,
+        })
+    }
+
+    /// Amplification coefficient A.
+    pub fn amplification(&self) -> f64 {
+        self.amplification
+    }
+
+    /// Peg price (Y per X).
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// A * n^n
+    #[inline]
+    fn ann(&self) -> f64 {
+        4.0 * self.amplification
+    }
+
+    /// Solve the invariant D for scaled balances.
+    fn compute_d(&self, x: f64, y: f64) -> f64 {
+        let sum = x + y;
+        if sum <= 0.0 || x <= 0.0 || y <= 0.0 {
+            return 0.0;
+        }
+
+        let ann = self.ann();
+        let mut d = sum;
+        for _ in 0..NEWTON_ITERATIONS {
+            let d_p = d * d * d / (4.0 * x * y);
+            let d_prev = d;
+            d = (ann * sum + 2.0 * d_p) * d / ((ann - 1.0) * d + 3.0 * d_p);
+            if (d - d_prev).abs() <= NEWTON_TOLERANCE * d {
+                break;
+            }
+        }
+        d
+    }
+
+    /// Solve for the other scaled balance given one balance and D.
+    ///
+    /// The invariant is symmetric, so the same solve works in both directions.
+    fn compute_other(&self, balance: f64, d: f64) -> f64 {
+        if balance <= 0.0 {
+            return f64::INFINITY;
+        }
+
+        let ann = self.ann();
+        let c = d * d / (2.0 * balance) * d / (2.0 * ann);
+        let b = balance + d / ann;
+        let mut other = d;
+        for _ in 0..NEWTON_ITERATIONS {
+            let prev = other;
+            other = (other * other + c) / (2.0 * other + b - d);
+            if (other - prev).abs() <= NEWTON_TOLERANCE * other {
+                break;
+            }
+        }
+        other
+    }
+
+    /// Marginal price of scaled X in Y at scaled balances (x, y) on invariant D.
+    #[inline]
+    fn scaled_price(&self, x: f64, y: f64, d: f64) -> f64 {
+        let ann = self.ann();
+        let d3 = d * d * d;
+        (ann + d3 / (4.0 * x * x * y)) / (ann + d3 / (4.0 * x * y * y))
+    }
+}
+
+impl PricingCurve for StableSwap {
+    fn kind(&self) -> CurveKind {
+        CurveKind::StableSwap {
+            amplification: self.amplification,
+        }
+    }
+
+    fn invariant(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        self.compute_d(reserve_x * self.rate, reserve_y)
+    }
+
+    fn spot_price(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        if reserve_x <= 0.0 || reserve_y <= 0.0 {
+            return 0.0;
+        }
+        let x = reserve_x * self.rate;
+        let d = self.compute_d(x, reserve_y);
+        self.rate * self.scaled_price(x, reserve_y, d)
+    }
+
+    fn y_out_for_x_in(&self, reserve_x: f64, reserve_y: f64, x_in: f64) -> f64 {
+        let d = self.compute_d(reserve_x * self.rate, reserve_y);
+        let new_y = self.compute_other((reserve_x + x_in) * self.rate, d);
+        reserve_y - new_y
+    }
+
+    fn x_out_for_y_in(&self, reserve_x: f64, reserve_y: f64, y_in: f64) -> f64 {
+        let d = self.compute_d(reserve_x * self.rate, reserve_y);
+        let new_x = self.compute_other(reserve_y + y_in, d) / self.rate;
+        reserve_x - new_x
+    }
+
+    fn y_in_for_x_out(&self, reserve_x: f64, reserve_y: f64, x_out: f64) -> f64 {
+        if x_out >= reserve_x {
+            return f64::INFINITY;
+        }
+        let d = self.compute_d(reserve_x * self.rate, reserve_y);
+        let new_y = self.compute_other((reserve_x - x_out) * self.rate, d);
+        new_y - reserve_y
+    }
+
+    fn x_in_for_y_out(&self, reserve_x: f64, reserve_y: f64, y_out: f64) -> f64 {
+        if y_out >= reserve_y {
+            return f64::INFINITY;
+        }
+        let d = self.compute_d(reserve_x * self.rate, reserve_y);
+        let new_x = self.compute_other(reserve_y - y_out, d) / self.rate;
+        new_x - reserve_x
+    }
+
+    /// Bisection on ln(x) along the current invariant; the marginal price
+    /// falls monotonically as X is added.
+    fn reserve_x_at_price(&self, reserve_x: f64, reserve_y: f64, price: f64) -> f64 {
+        let x = reserve_x * self.rate;
+        let d = self.compute_d(x, reserve_y);
+        let target = price / self.rate;
+        let price_at = |x: f64| self.scaled_price(x, self.compute_other(x, d), d);
+
+        let (mut lo, mut hi) = (x, x);
+        if price_at(x) > target {
+            for _ in 0..PRICE_ITERATIONS {
+                hi *= 2.0;
+                if price_at(hi) <= target {
+                    break;
+                }
+            }
+        } else {
+            for _ in 0..PRICE_ITERATIONS {
+                lo *= 0.5;
+                if price_at(lo) >= target {
+                    break;
+                }
+            }
+        }
+
+        for _ in 0..PRICE_ITERATIONS {
+            let mid = (lo * hi).sqrt();
+            if price_at(mid) > target {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+            if hi - lo <= NEWTON_TOLERANCE * hi {
+                break;
+            }
+        }
+        (lo * hi).sqrt() / self.rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced_pool_prices_at_peg() {
+        let curve = StableSwap::new(100.0, 1.0).unwrap();
+        assert!((curve.spot_price(1000.0, 1000.0) - 1.0).abs() < 1e-12);
+        assert!((curve.invariant(1000.0, 1000.0) - 2000.0).abs() < 1e-9);
+
+        let scaled = StableSwap::new(100.0, 100.0).unwrap();
+        assert!((scaled.spot_price(100.0, 10_000.0) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_flatter_than_constant_product_near_peg() {
+        let curve = StableSwap::new(100.0, 1.0).unwrap();
+        let y_out = curve.y_out_for_x_in(1000.0, 1000.0, 10.0);
+        let cp_out = 1000.0 - 1000.0 * 1000.0 / 1010.0;
+        assert!(y_out > cp_out);
+        assert!(y_out < 10.0);
+    }
+
+    #[test]
+    fn test_trades_preserve_invariant() {
+        let curve = StableSwap::new(50.0, 1.0).unwrap();
+        let (rx, ry) = (1200.0, 800.0);
+        let d = curve.invariant(rx, ry);
+
+        let y_out = curve.y_out_for_x_in(rx, ry, 25.0);
+        assert!((curve.invariant(rx + 25.0, ry - y_out) - d).abs() / d < 1e-12);
+
+        let x_in = curve.x_in_for_y_out(rx, ry, y_out);
+        assert!((x_in - 25.0).abs() < 1e-8);
+
+        let x_out = curve.x_out_for_y_in(rx, ry, 40.0);
+        let y_in = curve.y_in_for_x_out(rx, ry, x_out);
+        assert!((y_in - 40.0).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_reserve_at_price_hits_target() {
+        let curve = StableSwap::new(50.0, 1.0).unwrap();
+        let (rx, ry) = (1000.0, 1000.0);
+
+        for target in [0.9, 0.999, 1.001, 1.2] {
+            let new_x = curve.reserve_x_at_price(rx, ry, target);
+            let new_y = ry + curve.y_in_for_x_out(rx, ry, rx - new_x);
+            let spot = curve.spot_price(new_x, new_y);
+            assert!((spot - target).abs() < 1e-9, "target {}: {}", target, spot);
+        }
+    }
+}
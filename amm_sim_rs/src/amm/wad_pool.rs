@@ -0,0 +1,340 @@
+//! Integer constant product pool with on-chain rounding.
+//!
+//! Mirrors what a Solidity x * y = k pool computes with WAD fees and
+//! 18-decimal token amounts. Every division rounds in the pool's favour:
+//! - amounts paid out to the trader round down
+//! - amounts the trader must pay in round up
+//! - fees round up; the protocol share of a fee rounds down
+//!
+//! Intermediate products are taken in U256 so that reserve products do
+//! not overflow.
+
+use revm::primitives::U256;
+
+use crate::amm::cfmm::FeeMode;
+use crate::types::wad::{Wad, WAD};
+
+/// Swap amounts computed in integer math.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WadSwap {
+    /// Amount the trader pays in
+    pub amount_in: Wad,
+    /// Amount the trader receives
+    pub amount_out: Wad,
+    /// Fee charged, in the input token (or the output token under `FeeMode::Output`)
+    pub fee: Wad,
+}
+
+/// Pool balances held as WAD integers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WadPool {
+    pub reserve_x: Wad,
+    pub reserve_y: Wad,
+    /// LP fees collected outside the reserves
+    pub fees_x: Wad,
+    pub fees_y: Wad,
+    /// Protocol (treasury) fees
+    pub protocol_fees_x: Wad,
+    pub protocol_fees_y: Wad,
+}
+
+#[inline]
+fn to_u256(value: Wad) -> U256 {
+    U256::from(value.0.max(0) as u128)
+}
+
+#[inline]
+fn from_u256(value: U256) -> Option<Wad> {
+    let raw: u128 = value.try_into().ok()?;
+    i128::try_from(raw).ok().map(Wad)
+}
+
+/// floor(a * b / d)
+#[inline]
+fn mul_div_down(a: Wad, b: Wad, d: Wad) -> Option<Wad> {
+    if d.0 <= 0 {
+        return None;
+    }
+    from_u256(to_u256(a) * to_u256(b) / to_u256(d))
+}
+
+/// ceil(a * b / d)
+#[inline]
+fn mul_div_up(a: Wad, b: Wad, d: Wad) -> Option<Wad> {
+    if d.0 <= 0 {
+        return None;
+    }
+    let d = to_u256(d);
+    from_u256((to_u256(a) * to_u256(b)).div_ceil(d))
+}
+
+impl WadPool {
+    /// Create a pool with the given reserves and empty fee buckets.
+    pub fn new(reserve_x: Wad, reserve_y: Wad) -> Self {
+        Self {
+            reserve_x,
+            reserve_y,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    fn reserves_for(&self, x_in: bool) -> (Wad, Wad) {
+        if x_in {
+            (self.reserve_x, self.reserve_y)
+        } else {
+            (self.reserve_y, self.reserve_x)
+        }
+    }
+
+    /// Quote an exact-input swap. `x_in` is true when the trader pays X.
+    ///
+    /// Returns None if the swap is empty or the fee consumes the whole trade.
+    pub fn quote_exact_in(
+        &self,
+        x_in: bool,
+        amount_in: Wad,
+        fee: Wad,
+        fee_mode: FeeMode,
+    ) -> Option<WadSwap> {
+        if amount_in.0 <= 0 || fee.0 >= WAD {
+            return None;
+        }
+        let (reserve_in, reserve_out) = self.reserves_for(x_in);
+        let wad = Wad::one();
+
+        let (amount_out, fee_amount) = if fee_mode.fee_on_input() {
+            let fee_amount = mul_div_up(amount_in, fee, wad)?;
+            let net_in = amount_in - fee_amount;
+            let out = mul_div_down(net_in, reserve_out, reserve_in + net_in)?;
+            (out, fee_amount)
+        } else {
+            let gross_out = mul_div_down(amount_in, reserve_out, reserve_in + amount_in)?;
+            let fee_amount = mul_div_up(gross_out, fee, wad)?;
+            (gross_out - fee_amount, fee_amount)
+        };
+
+        if amount_out.0 <= 0 {
+            return None;
+        }
+        Some(WadSwap {
+            amount_in,
+            amount_out,
+            fee: fee_amount,
+        })
+    }
+
+    /// Quote an exact-output swap. `x_in` is true when the trader pays X.
+    ///
+    /// Returns None if the output would drain the reserve.
+    pub fn quote_exact_out(
+        &self,
+        x_in: bool,
+        amount_out: Wad,
+        fee: Wad,
+        fee_mode: FeeMode,
+    ) -> Option<WadSwap> {
+        if amount_out.0 <= 0 || fee.0 >= WAD {
+            return None;
+        }
+        let (reserve_in, reserve_out) = self.reserves_for(x_in);
+        let wad = Wad::one();
+        let gamma = Wad(WAD - fee.0);
+
+        let (amount_in, fee_amount) = if fee_mode.fee_on_input() {
+            if amount_out >= reserve_out {
+                return None;
+            }
+            let net_in = mul_div_up(reserve_in, amount_out, reserve_out - amount_out)?;
+            let total_in = mul_div_up(net_in, wad, gamma)?;
+            (total_in, total_in - net_in)
+        } else {
+            let gross_out = mul_div_up(amount_out, wad, gamma)?;
+            if gross_out >= reserve_out {
+                return None;
+            }
+            let amount_in = mul_div_up(reserve_in, gross_out, reserve_out - gross_out)?;
+            (amount_in, gross_out - amount_out)
+        };
+
+        if amount_in.0 <= 0 {
+            return None;
+        }
+        Some(WadSwap {
+            amount_in,
+            amount_out,
+            fee: fee_amount,
+        })
+    }
+
+    /// Settle a quoted swap into the balances.
+    ///
+    /// `protocol_fraction` is the protocol share of the fee as a WAD.
+    pub fn apply(&mut self, x_in: bool, swap: &WadSwap, fee_mode: FeeMode, protocol_fraction: Wad) {
+        let fee_on_input = fee_mode.fee_on_input();
+        let (reserve_in, reserve_out) = if x_in {
+            (&mut self.reserve_x, &mut self.reserve_y)
+        } else {
+            (&mut self.reserve_y, &mut self.reserve_x)
+        };
+        if fee_on_input {
+            *reserve_in = *reserve_in + swap.amount_in - swap.fee;
+            *reserve_out = *reserve_out - swap.amount_out;
+        } else {
+            *reserve_in = *reserve_in + swap.amount_in;
+            *reserve_out = *reserve_out - swap.amount_out - swap.fee;
+        }
+
+        let protocol_amount =
+            mul_div_down(swap.fee, protocol_fraction, Wad::one()).unwrap_or(Wad(0));
+        let lp_amount = swap.fee - protocol_amount;
+        let fee_in_x = x_in == fee_on_input;
+        let (reserve, lp_bucket, protocol_bucket) = if fee_in_x {
+            (
+                &mut self.reserve_x,
+                &mut self.fees_x,
+                &mut self.protocol_fees_x,
+            )
+        } else {
+            (
+                &mut self.reserve_y,
+                &mut self.fees_y,
+                &mut self.protocol_fees_y,
+            )
+        };
+        if fee_mode == FeeMode::Compounding {
+            *reserve = *reserve + lp_amount;
+        } else {
+            *lp_bucket = *lp_bucket + lp_amount;
+        }
+        *protocol_bucket = *protocol_bucket + protocol_amount;
+    }
+
+    /// Grow the reserves and LP fee buckets by `fraction` (a WAD).
+    ///
+    /// Returns the (x, y) the depositor pays, rounded up.
+    pub fn deposit(&mut self, fraction: Wad) -> Option<(Wad, Wad)> {
+        if fraction.0 <= 0 {
+            return None;
+        }
+        let one = Wad::one();
+        let mut paid = (Wad::zero(), Wad::zero());
+        for (balance, in_x) in [
+            (&mut self.reserve_x, true),
+            (&mut self.fees_x, true),
+            (&mut self.reserve_y, false),
+            (&mut self.fees_y, false),
+        ] {
+            let amount = mul_div_up(*balance, fraction, one)?;
+            *balance = *balance + amount;
+            if in_x {
+                paid.0 = paid.0 + amount;
+            } else {
+                paid.1 = paid.1 + amount;
+            }
+        }
+        Some(paid)
+    }
+
+    /// Shrink the reserves and LP fee buckets by `fraction` (a WAD, below one).
+    ///
+    /// Returns the (x, y) paid out, rounded down.
+    pub fn withdraw(&mut self, fraction: Wad) -> Option<(Wad, Wad)> {
+        if fraction.0 <= 0 || fraction.0 >= WAD {
+            return None;
+        }
+        let one = Wad::one();
+        let mut paid = (Wad::zero(), Wad::zero());
+        for (balance, in_x) in [
+            (&mut self.reserve_x, true),
+            (&mut self.fees_x, true),
+            (&mut self.reserve_y, false),
+            (&mut self.fees_y, false),
+        ] {
+            let amount = mul_div_down(*balance, fraction, one)?;
+            *balance = *balance - amount;
+            if in_x {
+                paid.0 = paid.0 + amount;
+            } else {
+                paid.1 = paid.1 + amount;
+            }
+        }
+        Some(paid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> WadPool {
+        WadPool::new(Wad::from_f64(1000.0), Wad::from_f64(1000.0))
+    }
+
+    #[test]
+    fn test_exact_in_matches_uniswap_formula() {
+        let pool = pool();
+        let fee = Wad::from_bps(30);
+        let amount_in = Wad::from_f64(10.0);
+        let swap = pool
+            .quote_exact_in(true, amount_in, fee, FeeMode::Input)
+            .unwrap();
+
+        // fee = ceil(10e18 * 3e15 / 1e18), out = floor(net * r_out / (r_in + net))
+        assert_eq!(swap.fee.0, 30_000_000_000_000_000);
+        let net = amount_in.0 - swap.fee.0;
+        let expected = (U256::from(net as u128) * U256::from(1000 * WAD as u128))
+            / U256::from((1000 * WAD + net) as u128);
+        assert_eq!(U256::from(swap.amount_out.0 as u128), expected);
+    }
+
+    #[test]
+    fn test_rounding_favours_pool() {
+        let pool = WadPool::new(Wad(1_000_003), Wad(2_000_011));
+        let fee = Wad::from_bps(30);
+        for mode in [FeeMode::Input, FeeMode::Output, FeeMode::Compounding] {
+            for x_in in [true, false] {
+                let swap = pool.quote_exact_out(x_in, Wad(777), fee, mode).unwrap();
+                // Paying the quoted input never yields less than the requested output,
+                // and paying one unit less never yields more than requested.
+                let back = pool
+                    .quote_exact_in(x_in, swap.amount_in, fee, mode)
+                    .unwrap();
+                assert!(back.amount_out >= swap.amount_out);
+
+                let mut after = pool;
+                after.apply(x_in, &swap, mode, Wad(0));
+                let k_before = to_u256(pool.reserve_x) * to_u256(pool.reserve_y);
+                let k_after = to_u256(after.reserve_x) * to_u256(after.reserve_y);
+                assert!(k_after >= k_before);
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_splits_protocol_fee() {
+        let mut pool = pool();
+        let fee = Wad::from_bps(30);
+        let swap = pool
+            .quote_exact_in(false, Wad::from_f64(10.0), fee, FeeMode::Input)
+            .unwrap();
+        pool.apply(false, &swap, FeeMode::Input, Wad::from_f64(0.25));
+
+        assert_eq!(pool.protocol_fees_y.0, swap.fee.0 / 4);
+        assert_eq!(pool.fees_y.0 + pool.protocol_fees_y.0, swap.fee.0);
+        assert_eq!(pool.reserve_y.0, 1000 * WAD + swap.amount_in.0 - swap.fee.0);
+        assert_eq!(pool.reserve_x.0, 1000 * WAD - swap.amount_out.0);
+    }
+
+    #[test]
+    fn test_liquidity_round_trip_never_pays_out_more() {
+        let mut pool = WadPool::new(Wad(1_000_003), Wad(2_000_011));
+        pool.fees_x = Wad(17);
+        let fraction = Wad::from_f64(0.3);
+        let (in_x, in_y) = pool.deposit(fraction).unwrap();
+        let share = Wad::from_f64(0.3 / 1.3);
+        let (out_x, out_y) = pool.withdraw(share).unwrap();
+        assert!(out_x <= in_x && out_y <= in_y);
+        assert!(pool.withdraw(Wad::one()).is_none());
+    }
+}
@@ -0,0 +1,157 @@
+//! Weighted product curve (Balancer-style, x^w * y^(1-w) = k).
+
+use crate::amm::curve::{CurveKind, PricingCurve};
+
+/// Two-asset weighted product curve.
+///
+/// `weight` is the weight of X; Y carries `1 - weight`. With weight 0.5
+/// this reduces to the constant product curve.
+#[derive(Debug, Clone, Copy)]
+pub struct WeightedProduct {
+    weight: f64,
+}
+
+impl WeightedProduct {
+    /// Create a weighted product curve. `weight` must be in (0, 1).
+    pub fn new(weight: f64) -> Result<Self, String> {
+        if !(weight > 0.0 && weight < 1.0) {
+            return Err(format!("pool weight must be in (0, 1), got {}", weight));
+        }
+        Ok(Self { weight })
+    }
+
+    /// Weight of X.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Exponent w / (1 - w) applied to X ratios.
+    #[inline]
+    fn x_exponent(&self) -> f64 {
+        self.weight / (1.0 - self.weight)
+    }
+}
+
+impl PricingCurve for WeightedProduct {
+    fn kind(&self) -> CurveKind {
+        CurveKind::Weighted {
+            weight: self.weight,
+        }
+    }
+
+    #[inline]
+    fn invariant(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        reserve_x.powf(self.weight) * reserve_y.powf(1.0 - self.weight)
+    }
+
+    /// p = (w / (1 - w)) * y / x
+    #[inline]
+    fn spot_price(&self, reserve_x: f64, reserve_y: f64) -> f64 {
+        if reserve_x == 0.0 {
+            return 0.0;
+        }
+        self.x_exponent() * reserve_y / reserve_x
+    }
+
+    /// Δy = y * (1 - (x / (x + Δx))^(w / (1 - w)))
+    #[inline]
+    fn y_out_for_x_in(&self, reserve_x: f64, reserve_y: f64, x_in: f64) -> f64 {
+        let ratio = reserve_x / (reserve_x + x_in);
+        reserve_y * (1.0 - ratio.powf(self.x_exponent()))
+    }
+
+    /// Δx = x * (1 - (y / (y + Δy))^((1 - w) / w))
+    #[inline]
+    fn x_out_for_y_in(&self, reserve_x: f64, reserve_y: f64, y_in: f64) -> f64 {
+        let ratio = reserve_y / (reserve_y + y_in);
+        reserve_x * (1.0 - ratio.powf(1.0 / self.x_exponent()))
+    }
+
+    /// Δy = y * ((x / (x - Δx))^(w / (1 - w)) - 1)
+    #[inline]
+    fn y_in_for_x_out(&self, reserve_x: f64, reserve_y: f64, x_out: f64) -> f64 {
+        if x_out >= reserve_x {
+            return f64::INFINITY;
+        }
+        let ratio = reserve_x / (reserve_x - x_out);
+        reserve_y * (ratio.powf(self.x_exponent()) - 1.0)
+    }
+
+    /// Δx = x * ((y / (y - Δy))^((1 - w) / w) - 1)
+    #[inline]
+    fn x_in_for_y_out(&self, reserve_x: f64, reserve_y: f64, y_out: f64) -> f64 {
+        if y_out >= reserve_y {
+            return f64::INFINITY;
+        }
+        let ratio = reserve_y / (reserve_y - y_out);
+        reserve_x * (ratio.powf(1.0 / self.x_exponent()) - 1.0)
+    }
+
+    /// x* = x * (p_spot / p)^(1 - w)
+    #[inline]
+    fn reserve_x_at_price(&self, reserve_x: f64, reserve_y: f64, price: f64) -> f64 {
+        let spot = self.spot_price(reserve_x, reserve_y);
+        reserve_x * (spot / price).powf(1.0 - self.weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm::curve::ConstantProduct;
+
+    #[test]
+    fn test_rejects_invalid_weight() {
+        assert!(WeightedProduct::new(0.0).is_err());
+        assert!(WeightedProduct::new(1.0).is_err());
+        assert!(WeightedProduct::new(f64::NAN).is_err());
+        assert!(WeightedProduct::new(0.8).is_ok());
+    }
+
+    #[test]
+    fn test_half_weight_matches_constant_product() {
+        let weighted = WeightedProduct::new(0.5).unwrap();
+        let cp = ConstantProduct;
+        let (rx, ry) = (1000.0, 1500.0);
+
+        assert!((weighted.spot_price(rx, ry) - cp.spot_price(rx, ry)).abs() < 1e-12);
+        assert!(
+            (weighted.y_out_for_x_in(rx, ry, 10.0) - cp.y_out_for_x_in(rx, ry, 10.0)).abs() < 1e-9
+        );
+        assert!(
+            (weighted.x_out_for_y_in(rx, ry, 10.0) - cp.x_out_for_y_in(rx, ry, 10.0)).abs() < 1e-9
+        );
+        assert!(
+            (weighted.reserve_x_at_price(rx, ry, 2.0) - cp.reserve_x_at_price(rx, ry, 2.0)).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_trades_preserve_invariant() {
+        let curve = WeightedProduct::new(0.8).unwrap();
+        let (rx, ry) = (100.0, 2500.0);
+        let k = curve.invariant(rx, ry);
+
+        let y_out = curve.y_out_for_x_in(rx, ry, 5.0);
+        assert!((curve.invariant(rx + 5.0, ry - y_out) - k).abs() / k < 1e-12);
+
+        let y_in = curve.y_in_for_x_out(rx, ry, 5.0);
+        assert!((curve.invariant(rx - 5.0, ry + y_in) - k).abs() / k < 1e-12);
+
+        let x_in = curve.x_in_for_y_out(rx, ry, y_out);
+        assert!((x_in - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reserve_at_price_hits_target() {
+        let curve = WeightedProduct::new(0.8).unwrap();
+        let (rx, ry) = (100.0, 2500.0);
+        let target = 1.3 * curve.spot_price(rx, ry);
+
+        let new_x = curve.reserve_x_at_price(rx, ry, target);
+        let y_in = curve.y_in_for_x_out(rx, ry, rx - new_x);
+        let new_spot = curve.spot_price(new_x, ry + y_in);
+        assert!((new_spot - target).abs() / target < 1e-9);
+    }
+}
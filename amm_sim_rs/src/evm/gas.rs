@@ -0,0 +1,138 @@
+//! Gas accounting for strategy callbacks.
+
+use std::collections::HashMap;
+
+use crate::types::result::GasStats;
+
+/// Strategy callbacks that gas is recorded for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Callback {
+    AfterInitialize,
+    AfterInitializeV2,
+    AfterSwap,
+    AfterSwapV2,
+    BeforeSwap,
+    OnStep,
+}
+
+impl Callback {
+    pub const ALL: [Callback; 6] = [
+        Callback::AfterInitialize,
+        Callback::AfterInitializeV2,
+        Callback::AfterSwap,
+        Callback::AfterSwapV2,
+        Callback::BeforeSwap,
+        Callback::OnStep,
+    ];
+
+    /// Solidity function name, used as the key in results.
+    pub fn name(self) -> &'static str {
+        match self {
+            Callback::AfterInitialize => "afterInitialize",
+            Callback::AfterInitializeV2 => "afterInitializeV2",
+            Callback::AfterSwap => "afterSwap",
+            Callback::AfterSwapV2 => "afterSwapV2",
+            Callback::BeforeSwap => "beforeSwap",
+            Callback::OnStep => "onStep",
+        }
+    }
+}
+
+/// Gas used by every committed callback, in call order.
+///
+/// Only execution gas is recorded: the intrinsic transaction cost (21000
+/// plus calldata) is left out, as a pool calling its strategy would not pay
+/// it. Gas limits in `EvmConfig` still cover the whole transaction.
+/// Reverted and out-of-gas calls are recorded too: their gas is spent
+/// on-chain all the same.
+#[derive(Debug, Clone, Default)]
+pub struct GasRecorder {
+    samples: [Vec<u64>; Callback::ALL.len()],
+}
+
+impl GasRecorder {
+    /// Record one call.
+    #[inline]
+    pub fn record(&mut self, callback: Callback, gas_used: u64) {
+        self.samples[callback as usize].push(gas_used);
+    }
+
+    /// Gas used by each call of `callback`.
+    pub fn samples(&self, callback: Callback) -> &[u64] {
+        &self.samples[callback as usize]
+    }
+
+    /// Total gas over all callbacks.
+    pub fn total(&self) -> u64 {
+        self.samples.iter().flatten().sum()
+    }
+
+    /// Append another recorder's samples (e.g. the same strategy on another pool).
+    pub fn merge(&mut self, other: &GasRecorder) {
+        for (mine, theirs) in self.samples.iter_mut().zip(other.samples.iter()) {
+            mine.extend_from_slice(theirs);
+        }
+    }
+
+    /// Forget all samples.
+    pub fn clear(&mut self) {
+        for samples in self.samples.iter_mut() {
+            samples.clear();
+        }
+    }
+
+    /// Summary of `callback`, or None if it was never called.
+    pub fn stats(&self, callback: Callback) -> Option<GasStats> {
+        let samples = self.samples(callback);
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        // Nearest-rank percentile
+        let percentile = |q: f64| {
+            let rank = (q * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        let total: u64 = sorted.iter().sum();
+        Some(GasStats {
+            count: sorted.len(),
+            total,
+            min: sorted[0],
+            mean: total as f64 / sorted.len() as f64,
+            max: sorted[sorted.len() - 1],
+            p50: percentile(0.50),
+            p90: percentile(0.90),
+            p99: percentile(0.99),
+        })
+    }
+
+    /// Summaries of every callback that was called, keyed by Solidity name.
+    pub fn stats_by_callback(&self) -> HashMap<String, GasStats> {
+        Callback::ALL
+            .iter()
+            .filter_map(|&callback| Some((callback.name().to_string(), self.stats(callback)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_percentiles() {
+        let mut recorder = GasRecorder::default();
+        for gas in (1..=100).rev() {
+            recorder.record(Callback::AfterSwap, gas * 1_000);
+        }
+        recorder.record(Callback::AfterInitialize, 50_000);
+
+        let stats = recorder.stats(Callback::AfterSwap).unwrap();
+        assert_eq!((stats.count, stats.min, stats.max), (100, 1_000, 100_000));
+        assert_eq!((stats.p50, stats.p90, stats.p99), (50_000, 90_000, 99_000));
+        assert_eq!(stats.mean, 50_500.0);
+        assert_eq!(recorder.total(), 5_050_000 + 50_000);
+        assert!(recorder.stats(Callback::OnStep).is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! Runtime enforcement of the fixed strategy storage layout.
+
+use std::fmt;
+
+use revm::{
+    interpreter::{
+        opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Gas, InstructionResult,
+        Interpreter, InterpreterResult, OpCode,
+    },
+    primitives::{Address, Bytes, B256, U256},
+    Database, EvmContext, Inspector,
+};
+
+use crate::evm::strategy::STRATEGY_SLOTS;
+
+/// What a guarded strategy call attempted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessViolation {
+    /// SSTORE to a slot outside the `slots` array
+    StorageWrite { slot: U256 },
+    /// Call, self-destruct, or balance/code read of another account
+    AccountAccess {
+        opcode: &'static str,
+        address: Address,
+    },
+    /// CREATE or CREATE2 of a new contract
+    Create,
+}
+
+impl fmt::Display for AccessViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessViolation::StorageWrite { slot } => {
+                write!(
+                    f,
+                    "SSTORE to slot {:#x} outside slots[0..{}]",
+                    slot, STRATEGY_SLOTS
+                )
+            }
+            AccessViolation::AccountAccess { opcode, address } => {
+                write!(f, "{} touching account {}", opcode, address)
+            }
+            AccessViolation::Create => write!(f, "contract creation"),
+        }
+    }
+}
+
+/// Inspector that stops a strategy at its first write outside `slots` or
+/// its first access to another account.
+///
+/// The offending instruction is not executed: the frame reverts instead,
+/// so none of the call's state changes survive. Precompile calls are
+/// allowed. After a violation the caller must treat the call as failed,
+/// whatever its result; `take_violation` says why.
+#[derive(Debug)]
+pub struct StorageGuard {
+    /// The strategy, whose own storage and code may be used
+    strategy: Address,
+    /// First violation of the current call
+    violation: Option<AccessViolation>,
+}
+
+impl StorageGuard {
+    pub fn new(strategy: Address) -> Self {
+        Self {
+            strategy,
+            violation: None,
+        }
+    }
+
+    /// Take the violation of the last call, if any.
+    pub fn take_violation(&mut self) -> Option<AccessViolation> {
+        self.violation.take()
+    }
+
+    /// Record a violation, keeping the first one.
+    fn flag(&mut self, violation: AccessViolation) {
+        self.violation.get_or_insert(violation);
+    }
+
+    /// Violation the instruction about to run would commit, if any.
+    fn check(&self, interp: &Interpreter) -> Option<AccessViolation> {
+        let op = interp.current_opcode();
+        let target = interp.stack().peek(0).ok()?;
+        match op {
+            opcode::SSTORE if target >= U256::from(STRATEGY_SLOTS) => {
+                Some(AccessViolation::StorageWrite { slot: target })
+            }
+            opcode::BALANCE
+            | opcode::EXTCODESIZE
+            | opcode::EXTCODECOPY
+            | opcode::EXTCODEHASH
+            | opcode::SELFDESTRUCT => {
+                let address = Address::from_word(B256::from(target.to_be_bytes()));
+                (address != self.strategy).then(|| AccessViolation::AccountAccess {
+                    opcode: OpCode::new(op).map_or("UNKNOWN", OpCode::as_str),
+                    address,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Outcome that fails a blocked call or create without running it.
+fn blocked(gas_limit: u64) -> InterpreterResult {
+    InterpreterResult::new(InstructionResult::Revert, Bytes::new(), Gas::new(gas_limit))
+}
+
+impl<DB: Database> Inspector<DB> for StorageGuard {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        if let Some(violation) = self.check(interp) {
+            self.flag(violation);
+        }
+        // Also unwinds frames that continue after a blocked call
+        if self.violation.is_some() {
+            interp.instruction_result = InstructionResult::Revert;
+        }
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        let address = inputs.bytecode_address;
+        if address == self.strategy || context.precompiles.contains(&address) {
+            return None;
+        }
+        self.flag(AccessViolation::AccountAccess {
+            opcode: "CALL",
+            address,
+        });
+        Some(CallOutcome::new(
+            blocked(inputs.gas_limit),
+            inputs.return_memory_offset.clone(),
+        ))
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.flag(AccessViolation::Create);
+        Some(CreateOutcome::new(blocked(inputs.gas_limit), None))
+    }
+}
+
+/// Inspector that runs `inner` under an optional storage guard, which has
+/// the last word on each instruction and the first on calls and creates.
+pub struct Guarded<I> {
+    pub inner: I,
+    pub guard: Option<StorageGuard>,
+}
+
+impl<DB: Database, I: Inspector<DB>> Inspector<DB> for Guarded<I> {
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.inner.step(interp, context);
+        if let Some(guard) = self.guard.as_mut() {
+            guard.step(interp, context);
+        }
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        self.inner.step_end(interp, context);
+    }
+
+    fn call(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        match self.guard.as_mut() {
+            Some(guard) => guard.call(context, inputs),
+            None => None,
+        }
+        .or_else(|| self.inner.call(context, inputs))
+    }
+
+    fn create(
+        &mut self,
+        context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        match self.guard.as_mut() {
+            Some(guard) => guard.create(context, inputs),
+            None => None,
+        }
+        .or_else(|| self.inner.create(context, inputs))
+    }
+}
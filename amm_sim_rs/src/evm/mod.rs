@@ -1,5 +1,17 @@
 //! EVM execution module using revm.
 
+pub mod gas;
+pub mod guard;
+pub mod revert;
 pub mod strategy;
+#[cfg(any(test, feature = "bench"))]
+pub mod test_support;
+pub mod trace;
+pub mod validator;
 
-pub use strategy::EVMStrategy;
+pub use gas::{Callback, GasRecorder};
+pub use guard::{AccessViolation, StorageGuard};
+pub use revert::{Revert, RevertReason};
+pub use strategy::{slot_to_f64, DeployedStrategy, EVMStrategy, PendingState, STRATEGY_SLOTS};
+pub use trace::{CallbackTrace, OpcodeStep, StorageAccess};
+pub use validator::{validate_runtime_code, Violation};
@@ -0,0 +1,192 @@
+//! Decoding of strategy revert data.
+
+use std::fmt;
+
+use revm::primitives::U256;
+
+/// Selector of `Error(string)`, used by `revert("...")` and `require(..., "...")`.
+const SELECTOR_ERROR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Selector of `Panic(uint256)`, raised by failed assertions and checked arithmetic.
+const SELECTOR_PANIC: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Why a strategy call reverted, decoded from its revert data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevertReason {
+    /// `Error(string)` with its message
+    Error(String),
+    /// `Panic(uint256)` with its code, e.g. 0x11 overflow, 0x12 division by
+    /// zero, 0x32 array index out of bounds
+    Panic(u64),
+    /// Custom error: selector and ABI-encoded arguments
+    Custom { selector: [u8; 4], args: Vec<u8> },
+    /// Revert without data (`revert()` or `require(cond)`)
+    Empty,
+    /// Data too short for a selector, or a malformed `Error`/`Panic` payload
+    Malformed(Vec<u8>),
+}
+
+impl RevertReason {
+    /// Decode revert data.
+    pub fn decode(data: &[u8]) -> Self {
+        if data.is_empty() {
+            return RevertReason::Empty;
+        }
+        let Some((selector, args)) = data.split_first_chunk::<4>() else {
+            return RevertReason::Malformed(data.to_vec());
+        };
+        let decoded = match *selector {
+            SELECTOR_ERROR => decode_string(args).map(RevertReason::Error),
+            SELECTOR_PANIC => decode_word(args).map(|code| RevertReason::Panic(saturate(code))),
+            _ => Some(RevertReason::Custom {
+                selector: *selector,
+                args: args.to_vec(),
+            }),
+        };
+        decoded.unwrap_or_else(|| RevertReason::Malformed(data.to_vec()))
+    }
+
+    /// Short machine-readable kind: "error", "panic", "custom", "empty" or "malformed".
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RevertReason::Error(_) => "error",
+            RevertReason::Panic(_) => "panic",
+            RevertReason::Custom { .. } => "custom",
+            RevertReason::Empty => "empty",
+            RevertReason::Malformed(_) => "malformed",
+        }
+    }
+}
+
+/// What a Solidity panic code means.
+pub fn panic_description(code: u64) -> &'static str {
+    match code {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array encoding",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to zero-initialized function",
+        _ => "unknown panic code",
+    }
+}
+
+impl fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevertReason::Error(message) => write!(f, "Error({:?})", message),
+            RevertReason::Panic(code) => {
+                write!(f, "Panic({:#04x}): {}", code, panic_description(*code))
+            }
+            RevertReason::Custom { selector, args } => write!(
+                f,
+                "custom error 0x{} with {} bytes of arguments",
+                hex(selector),
+                args.len()
+            ),
+            RevertReason::Empty => write!(f, "revert without reason"),
+            RevertReason::Malformed(data) => write!(f, "malformed revert data 0x{}", hex(data)),
+        }
+    }
+}
+
+/// A strategy revert and where it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revert {
+    /// Solidity function name of the failing call
+    pub callback: &'static str,
+    /// Simulation step, for callbacks made during a step
+    pub timestamp: Option<u64>,
+    /// Decoded revert data
+    pub reason: RevertReason,
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} reverted", self.callback)?;
+        if let Some(timestamp) = self.timestamp {
+            write!(f, " at step {}", timestamp)?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// Decode an ABI-encoded `string` argument.
+fn decode_string(args: &[u8]) -> Option<String> {
+    let offset = usize::try_from(decode_word(args)?).ok()?;
+    let length = usize::try_from(decode_word(args.get(offset..)?)?).ok()?;
+    let start = offset.checked_add(32)?;
+    let bytes = args.get(start..start.checked_add(length)?)?;
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Decode the first 32-byte word.
+fn decode_word(args: &[u8]) -> Option<U256> {
+    let word: &[u8; 32] = args.first_chunk()?;
+    Some(U256::from_be_bytes(*word))
+}
+
+fn saturate(value: U256) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(value: u64) -> [u8; 32] {
+        U256::from(value).to_be_bytes()
+    }
+
+    #[test]
+    fn test_decode_error_and_panic() {
+        let mut data = SELECTOR_ERROR.to_vec();
+        data.extend_from_slice(&word(32));
+        data.extend_from_slice(&word(5));
+        data.extend_from_slice(b"hello");
+        data.resize(4 + 96, 0);
+        assert_eq!(
+            RevertReason::decode(&data),
+            RevertReason::Error("hello".into())
+        );
+
+        let mut data = SELECTOR_PANIC.to_vec();
+        data.extend_from_slice(&word(0x12));
+        let reason = RevertReason::decode(&data);
+        assert_eq!(reason, RevertReason::Panic(0x12));
+        assert_eq!(
+            reason.to_string(),
+            "Panic(0x12): division or modulo by zero"
+        );
+    }
+
+    #[test]
+    fn test_decode_custom_empty_and_malformed() {
+        let data = [0xde, 0xad, 0xbe, 0xef, 0x01];
+        assert_eq!(
+            RevertReason::decode(&data),
+            RevertReason::Custom {
+                selector: [0xde, 0xad, 0xbe, 0xef],
+                args: vec![0x01],
+            }
+        );
+        assert_eq!(RevertReason::decode(&[]), RevertReason::Empty);
+        assert_eq!(
+            RevertReason::decode(&[0x01, 0x02]),
+            RevertReason::Malformed(vec![0x01, 0x02])
+        );
+        // Error(string) whose length runs past the data
+        let mut data = SELECTOR_ERROR.to_vec();
+        data.extend_from_slice(&word(32));
+        data.extend_from_slice(&word(100));
+        assert_eq!(RevertReason::decode(&data).kind(), "malformed");
+    }
+}
@@ -0,0 +1,84 @@
+//! Hand-assembled strategy contracts shared by unit tests and benches.
+
+/// Init code for a contract that returns a 5% fee pair from every call.
+pub const FIXED_FEE_INIT: [u8; 32] = [
+    // CODECOPY the 20-byte runtime at offset 12 and return it
+    0x60, 0x14, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x14, 0x60, 0x00, 0xf3,
+    // runtime: mem[0..64] = (5e16, 5e16); return mem[0..64]
+    0x66, 0xb1, 0xa2, 0xbc, 0x2e, 0xc5, 0x00, 0x00, 0x80, 0x60, 0x00, 0x52, 0x60, 0x20, 0x52, 0x60,
+    0x40, 0x60, 0x00, 0xf3,
+];
+
+/// Init code for a contract that, on any call, increments slot 0 and
+/// returns the new value twice (as a fee pair).
+pub const COUNTER_INIT: [u8; 34] = [
+    // CODECOPY the 22-byte runtime at offset 12 and return it
+    0x60, 0x16, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x16, 0x60, 0x00, 0xf3,
+    // runtime: slot0 += 1; mem[0..64] = (slot0, slot0); return mem[0..64]
+    0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x80, 0x60, 0x00, 0x55, 0x80, 0x60, 0x00, 0x52, 0x60, 0x20,
+    0x52, 0x60, 0x40, 0x60, 0x00, 0xf3,
+];
+
+/// Init code for a contract that returns (42, 42) wei of fee from every call
+/// except afterSwap, which reverts without data.
+pub const REVERT_AFTER_SWAP_INIT: [u8; 47] = [
+    // CODECOPY the 35-byte runtime at offset 12 and return it
+    0x60, 0x23, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x23, 0x60, 0x00, 0xf3,
+    // runtime: if selector == afterSwap jump to the revert
+    0x60, 0x00, 0x35, 0x60, 0xe0, 0x1c, 0x63, 0xc2, 0xba, 0xbb, 0x57, 0x14, 0x60, 0x1e, 0x57,
+    // mem[0..64] = (42, 42); return mem[0..64]
+    0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x2a, 0x60, 0x20, 0x52, 0x60, 0x40, 0x60, 0x00, 0xf3,
+    // revert()
+    0x5b, 0x60, 0x00, 0x80, 0xfd,
+];
+
+/// Init code for a contract that returns (0, 0) of fee from every call
+/// except beforeSwap, which counts its calls in slot 0 and quotes a 1% fee.
+pub const BEFORE_SWAP_INIT: [u8; 58] = [
+    // CODECOPY the 46-byte runtime at offset 12 and return it
+    0x60, 0x2e, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x2e, 0x60, 0x00, 0xf3,
+    // runtime: if selector == beforeSwap jump to the hook
+    0x60, 0x00, 0x35, 0x60, 0xe0, 0x1c, 0x63, 0xc4, 0x0c, 0x1b, 0x95, 0x14, 0x60, 0x14, 0x57,
+    // return mem[0..64] = (0, 0)
+    0x60, 0x40, 0x60, 0x00, 0xf3,
+    // hook: slot0 += 1; mem[0..32] = 1e16; return mem[0..32]
+    0x5b, 0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x66, 0x23, 0x86, 0xf2, 0x6f, 0xc1,
+    0x00, 0x00, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+];
+
+/// Init code for a contract that returns (0, 0) of fee from every call
+/// except beforeSwap, which reverts without data.
+pub const REVERT_BEFORE_SWAP_INIT: [u8; 37] = [
+    // CODECOPY the 25-byte runtime at offset 12 and return it
+    0x60, 0x19, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x19, 0x60, 0x00, 0xf3,
+    // runtime: if selector == beforeSwap jump to the hook
+    0x60, 0x00, 0x35, 0x60, 0xe0, 0x1c, 0x63, 0xc4, 0x0c, 0x1b, 0x95, 0x14, 0x60, 0x14, 0x57,
+    // return mem[0..64] = (0, 0)
+    0x60, 0x40, 0x60, 0x00, 0xf3, // hook: revert with no data
+    0x5b, 0x60, 0x00, 0x80, 0xfd,
+];
+
+/// Init code for a contract that posts the fee in slot 0 (initially zero)
+/// from every call, and whose `onStep` first sets that fee to 1%.
+pub const ON_STEP_FEE_INIT: [u8; 58] = [
+    // CODECOPY the 46-byte runtime at offset 12 and return it
+    0x60, 0x2e, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x2e, 0x60, 0x00, 0xf3,
+    // runtime: if selector == onStep jump to the hook
+    0x60, 0x00, 0x35, 0x60, 0xe0, 0x1c, 0x63, 0xa8, 0x73, 0x37, 0x67, 0x14, 0x60, 0x1f, 0x57,
+    // mem[0..64] = (slot0, slot0); return mem[0..64]
+    0x5b, 0x60, 0x00, 0x54, 0x80, 0x60, 0x00, 0x52, 0x60, 0x20, 0x52, 0x60, 0x40, 0x60, 0x00, 0xf3,
+    // hook: slot0 = 1e16; jump back to the return
+    0x5b, 0x66, 0x23, 0x86, 0xf2, 0x6f, 0xc1, 0x00, 0x00, 0x60, 0x00, 0x55, 0x60, 0x0f, 0x56,
+];
+
+/// `ON_STEP_FEE_INIT` with its onStep selector zeroed: the engine never
+/// sends it a heartbeat, so it posts a zero fee throughout.
+pub const ZERO_FEE_INIT: [u8; 58] = {
+    let mut init = ON_STEP_FEE_INIT;
+    let mut i = 19;
+    while i < 23 {
+        init[i] = 0;
+        i += 1;
+    }
+    init
+};
@@ -0,0 +1,200 @@
+//! Opt-in opcode-level tracing of strategy callbacks.
+
+use pyo3::prelude::*;
+use revm::{
+    interpreter::{opcode, Interpreter, OpCode},
+    primitives::{ExecutionResult, Output, U256},
+    Database, EvmContext, Inspector,
+};
+
+use crate::evm::gas::Callback;
+
+/// One executed opcode.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct OpcodeStep {
+    /// Program counter
+    #[pyo3(get)]
+    pub pc: usize,
+
+    /// Opcode byte
+    #[pyo3(get)]
+    pub opcode: u8,
+
+    /// Mnemonic, e.g. "SSTORE"
+    #[pyo3(get)]
+    pub name: String,
+
+    /// Gas charged for the opcode, including dynamic costs
+    #[pyo3(get)]
+    pub gas_cost: u64,
+}
+
+/// One SLOAD or SSTORE by the strategy.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct StorageAccess {
+    /// True for SSTORE, false for SLOAD
+    #[pyo3(get)]
+    pub is_write: bool,
+
+    /// Storage slot
+    pub slot: U256,
+
+    /// Value read or written
+    pub value: U256,
+}
+
+#[pymethods]
+impl StorageAccess {
+    /// Storage slot as a hex string.
+    #[getter]
+    fn slot(&self) -> String {
+        format!("{:#x}", self.slot)
+    }
+
+    /// Value read or written as a hex string.
+    #[getter]
+    fn value(&self) -> String {
+        format!("{:#x}", self.value)
+    }
+
+    fn __repr__(&self) -> String {
+        let op = if self.is_write { "SSTORE" } else { "SLOAD" };
+        format!("{}({:#x}) = {:#x}", op, self.slot, self.value)
+    }
+}
+
+/// Execution trace of one committed strategy callback.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct CallbackTrace {
+    /// Solidity function name of the callback
+    #[pyo3(get)]
+    pub callback: String,
+
+    /// ABI-encoded calldata, including the selector
+    #[pyo3(get)]
+    pub calldata: Vec<u8>,
+
+    /// Executed opcodes, in order
+    #[pyo3(get)]
+    pub opcodes: Vec<OpcodeStep>,
+
+    /// Storage reads and writes, in order
+    #[pyo3(get)]
+    pub storage: Vec<StorageAccess>,
+
+    /// Return data (revert data if the call reverted)
+    #[pyo3(get)]
+    pub return_data: Vec<u8>,
+
+    /// Execution gas used by the call, without the intrinsic transaction
+    /// cost (as in the gas stats)
+    #[pyo3(get)]
+    pub gas_used: u64,
+
+    /// Whether the call succeeded
+    #[pyo3(get)]
+    pub success: bool,
+}
+
+#[pymethods]
+impl CallbackTrace {
+    fn __repr__(&self) -> String {
+        format!(
+            "CallbackTrace({}, opcodes={}, storage={}, gas_used={}, success={})",
+            self.callback,
+            self.opcodes.len(),
+            self.storage.len(),
+            self.gas_used,
+            self.success
+        )
+    }
+}
+
+/// Inspector that records the opcodes and storage accesses of a call.
+#[derive(Debug, Default)]
+pub struct Tracer {
+    opcodes: Vec<OpcodeStep>,
+    storage: Vec<StorageAccess>,
+    /// Gas remaining before the current opcode
+    gas_before: u64,
+    /// Slot of an SLOAD whose value is on the stack after it executes
+    pending_sload: Option<U256>,
+}
+
+impl Tracer {
+    /// Package everything recorded since the last call into a trace.
+    pub fn finish(
+        &mut self,
+        callback: Callback,
+        calldata: &[u8],
+        result: &ExecutionResult,
+        gas_used: u64,
+    ) -> CallbackTrace {
+        let return_data = match result {
+            ExecutionResult::Success {
+                output: Output::Call(data),
+                ..
+            } => data.to_vec(),
+            ExecutionResult::Revert { output, .. } => output.to_vec(),
+            _ => Vec::new(),
+        };
+        self.pending_sload = None;
+        CallbackTrace {
+            callback: callback.name().to_string(),
+            calldata: calldata.to_vec(),
+            opcodes: std::mem::take(&mut self.opcodes),
+            storage: std::mem::take(&mut self.storage),
+            return_data,
+            gas_used,
+            success: result.is_success(),
+        }
+    }
+}
+
+impl<DB: Database> Inspector<DB> for Tracer {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        let op = interp.current_opcode();
+        self.gas_before = interp.gas.remaining();
+        self.opcodes.push(OpcodeStep {
+            pc: interp.program_counter(),
+            opcode: op,
+            name: OpCode::new(op)
+                .map_or("UNKNOWN", OpCode::as_str)
+                .to_string(),
+            gas_cost: 0,
+        });
+
+        let stack = interp.stack();
+        match op {
+            opcode::SLOAD => self.pending_sload = stack.peek(0).ok(),
+            opcode::SSTORE => {
+                if let (Ok(slot), Ok(value)) = (stack.peek(0), stack.peek(1)) {
+                    self.storage.push(StorageAccess {
+                        is_write: true,
+                        slot,
+                        value,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        if let Some(step) = self.opcodes.last_mut() {
+            step.gas_cost = self.gas_before.saturating_sub(interp.gas.remaining());
+        }
+        if let Some(slot) = self.pending_sload.take() {
+            if let Ok(value) = interp.stack().peek(0) {
+                self.storage.push(StorageAccess {
+                    is_write: false,
+                    slot,
+                    value,
+                });
+            }
+        }
+    }
+}
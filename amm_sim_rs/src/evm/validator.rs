@@ -0,0 +1,287 @@
+//! Static validation of deployed strategy bytecode.
+//!
+//! Source-level checks can be bypassed with inline assembly, so the runtime
+//! code is disassembled and every reachable instruction is checked against
+//! a denylist. Instruction boundaries follow the EVM's own decoding (PUSH
+//! immediates are skipped), and code after a terminating instruction is
+//! unreachable until the next JUMPDEST, so data never produces findings.
+
+use std::fmt;
+
+use revm::interpreter::{opcode, OpCode};
+
+/// Highest precompile address a strategy may STATICCALL (BLS12-381 precompiles
+/// end at 0x11).
+const MAX_PRECOMPILE: u8 = 0x11;
+
+/// Initial byte of a CBOR byte string (major type 2) with a zero length.
+const CBOR_BYTES: u8 = 0x40;
+/// Initial byte of a CBOR text string (major type 3) with a zero length.
+const CBOR_TEXT: u8 = 0x60;
+
+/// A forbidden instruction in a strategy's runtime code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Byte offset of the instruction
+    pub pc: usize,
+    /// Opcode byte
+    pub opcode: u8,
+    /// Mnemonic, e.g. "CALL"
+    pub name: &'static str,
+    /// Why the instruction is forbidden
+    pub reason: &'static str,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at pc {}: {}", self.name, self.pc, self.reason)
+    }
+}
+
+/// Why `op` is forbidden, or None if strategies may use it.
+///
+/// STATICCALL is handled separately, since calls to precompiles are allowed.
+fn forbidden_reason(op: u8) -> Option<&'static str> {
+    match op {
+        opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL => Some("calls another contract"),
+        opcode::CREATE | opcode::CREATE2 => Some("creates a contract"),
+        opcode::SELFDESTRUCT => Some("destroys the contract"),
+        opcode::LOG0..=opcode::LOG4 => Some("emits a log"),
+        opcode::BALANCE | opcode::EXTCODESIZE | opcode::EXTCODECOPY | opcode::EXTCODEHASH => {
+            Some("reads another account")
+        }
+        opcode::BLOCKHASH | opcode::COINBASE | opcode::DIFFICULTY => {
+            Some("reads block data the simulation does not model")
+        }
+        _ => None,
+    }
+}
+
+/// Whether execution cannot fall through to the next instruction.
+fn terminates(op: u8) -> bool {
+    matches!(
+        op,
+        opcode::STOP
+            | opcode::JUMP
+            | opcode::RETURN
+            | opcode::REVERT
+            | opcode::INVALID
+            | opcode::SELFDESTRUCT
+    )
+}
+
+/// Code without the Solidity metadata trailer.
+///
+/// solc appends `INVALID`, a CBOR map, and the map's length as two
+/// big-endian bytes. The map holds a source hash, arbitrary bytes that may
+/// look like a JUMPDEST and forbidden opcodes, so a trailer that parses as
+/// solc metadata is not checked. Anything else is checked as code, so a
+/// forged trailer cannot hide instructions.
+fn strip_metadata(code: &[u8]) -> &[u8] {
+    let Some((rest, length)) = code.split_last_chunk::<2>() else {
+        return code;
+    };
+    let length = u16::from_be_bytes(*length) as usize;
+    let Some(marker) = rest.len().checked_sub(length + 1) else {
+        return code;
+    };
+    if rest[marker] == opcode::INVALID && is_solc_metadata(&rest[marker + 1..]) {
+        &rest[..marker]
+    } else {
+        code
+    }
+}
+
+/// Whether `map` is exactly a CBOR map as solc emits it: a "solc" version
+/// and optionally a source hash ("ipfs", "bzzr0", "bzzr1") and the
+/// "experimental" flag, each key once.
+fn is_solc_metadata(map: &[u8]) -> bool {
+    let Some((&header, mut rest)) = map.split_first() else {
+        return false;
+    };
+    let entries = match header {
+        0xa1..=0xa4 => header - 0xa0,
+        _ => return false,
+    };
+    let mut keys: Vec<&[u8]> = Vec::new();
+    for _ in 0..entries {
+        let Some((key, after_key)) = cbor_string(rest, CBOR_TEXT) else {
+            return false;
+        };
+        if keys.contains(&key) {
+            return false;
+        }
+        let value = match key {
+            b"ipfs" => cbor_string(after_key, CBOR_BYTES)
+                .filter(|(hash, _)| hash.len() == 34 && hash.starts_with(&[0x12, 0x20])),
+            b"bzzr0" | b"bzzr1" => {
+                cbor_string(after_key, CBOR_BYTES).filter(|(hash, _)| hash.len() == 32)
+            }
+            b"solc" => cbor_string(after_key, CBOR_BYTES)
+                .filter(|(version, _)| version.len() == 3)
+                .or_else(|| cbor_string(after_key, CBOR_TEXT)),
+            // CBOR false or true
+            b"experimental" => match after_key.split_first() {
+                Some((0xf4 | 0xf5, after)) => Some((&after_key[..1], after)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some((_, after_value)) = value else {
+            return false;
+        };
+        keys.push(key);
+        rest = after_value;
+    }
+    rest.is_empty() && keys.contains(&b"solc".as_slice())
+}
+
+/// Split a CBOR string of the given major type (with a length under 256)
+/// off the front of `data`, returning its payload and the remaining bytes.
+fn cbor_string(data: &[u8], major: u8) -> Option<(&[u8], &[u8])> {
+    let (&initial, rest) = data.split_first()?;
+    let (length, rest) = match initial.checked_sub(major)? {
+        short @ 0..=23 => (short as usize, rest),
+        24 => {
+            let (&length, rest) = rest.split_first()?;
+            (length as usize, rest)
+        }
+        _ => return None,
+    };
+    (rest.len() >= length).then(|| rest.split_at(length))
+}
+
+/// Check deployed runtime code for forbidden instructions.
+///
+/// Returns every reachable forbidden instruction, in code order. STATICCALL
+/// is allowed only to a precompile pushed as a constant right before the
+/// gas argument (`PUSH <precompile> GAS STATICCALL`, as compiled from
+/// `staticcall(gas(), 0x02, ...)`); any other STATICCALL is flagged.
+pub fn validate_runtime_code(code: &[u8]) -> Vec<Violation> {
+    let code = strip_metadata(code);
+    let mut violations = Vec::new();
+    let mut reachable = true;
+    // The two previous instructions, with the immediate of a PUSH
+    let mut previous: [(u8, &[u8]); 2] = [(opcode::STOP, &[]); 2];
+
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = code[pc];
+        let immediate_len = match op {
+            opcode::PUSH1..=opcode::PUSH32 => (op - opcode::PUSH1 + 1) as usize,
+            _ => 0,
+        };
+        let immediate = &code[(pc + 1).min(code.len())..(pc + 1 + immediate_len).min(code.len())];
+
+        if op == opcode::JUMPDEST {
+            reachable = true;
+        }
+        if reachable {
+            let reason = match op {
+                opcode::STATICCALL if !calls_precompile(&previous) => {
+                    Some("calls a contract that is not a precompile")
+                }
+                _ => forbidden_reason(op),
+            };
+            if let Some(reason) = reason {
+                violations.push(Violation {
+                    pc,
+                    opcode: op,
+                    name: OpCode::new(op).map_or("UNKNOWN", OpCode::as_str),
+                    reason,
+                });
+            }
+            if terminates(op) {
+                reachable = false;
+            }
+        }
+
+        previous = [previous[1], (op, immediate)];
+        pc += 1 + immediate_len;
+    }
+    violations
+}
+
+/// Whether the two instructions before a STATICCALL are `PUSH <precompile> GAS`.
+fn calls_precompile(previous: &[(u8, &[u8]); 2]) -> bool {
+    let [(push, address), (gas, _)] = *previous;
+    if gas != opcode::GAS || !(opcode::PUSH1..=opcode::PUSH32).contains(&push) {
+        return false;
+    }
+    let Some((&last, high)) = address.split_last() else {
+        return false;
+    };
+    high.iter().all(|&byte| byte == 0) && (1..=MAX_PRECOMPILE).contains(&last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::primitives::hex;
+
+    #[test]
+    fn test_flags_reachable_forbidden_opcodes() {
+        let code = [
+            // PUSH2 0xf1f1 (immediate bytes are not instructions); LOG0
+            0x61, 0xf1, 0xf1, 0xa0, //
+            // PUSH1 0x02 GAS STATICCALL (precompile); PUSH1 0x20 GAS STATICCALL
+            0x60, 0x02, 0x5a, 0xfa, 0x60, 0x20, 0x5a, 0xfa, //
+            // STOP, then dead code until the JUMPDEST
+            0x00, 0xf0, 0x5b, 0xf4,
+        ];
+        let found: Vec<(usize, &str)> = validate_runtime_code(&code)
+            .iter()
+            .map(|v| (v.pc, v.name))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(3, "LOG0"), (11, "STATICCALL"), (15, "DELEGATECALL")]
+        );
+    }
+
+    /// Runtime code of a small contract compiled by solc 0.8.25 (`FastLz.sol`
+    /// from github.com/danyalprout/fastlz, as embedded in revm's tests),
+    /// ending in the standard "ipfs" + "solc" metadata trailer.
+    const SOLC_RUNTIME: [u8; 1115] = hex!("608060405234801561001057600080fd5b506004361061002b5760003560e01c8063920a769114610030575b600080fd5b61004361003e366004610374565b610055565b60405190815260200160405180910390f35b600061006082610067565b5192915050565b60606101e0565b818153600101919050565b600082840393505b838110156100a25782810151828201511860001a1590930292600101610081565b9392505050565b825b602082106100d75782516100c0601f8361006e565b5260209290920191601f19909101906021016100ab565b81156100a25782516100ec600184038361006e565b520160010192915050565b60006001830392505b61010782106101385761012a8360ff1661012560fd6101258760081c60e0018961006e565b61006e565b935061010682039150610100565b600782106101655761015e8360ff16610125600785036101258760081c60e0018961006e565b90506100a2565b61017e8360ff166101258560081c8560051b018761006e565b949350505050565b80516101d890838303906101bc90600081901a600182901a60081b1760029190911a60101b17639e3779b90260131c611fff1690565b8060021b6040510182815160e01c1860e01b8151188152505050565b600101919050565b5060405161800038823961800081016020830180600d8551820103826002015b81811015610313576000805b50508051604051600082901a600183901a60081b1760029290921a60101b91909117639e3779b9810260111c617ffc16909101805160e081811c878603811890911b9091189091528401908183039084841061026857506102a3565b600184019350611fff821161029d578251600081901a600182901a60081b1760029190911a60101b17810361029d57506102a3565b5061020c565b8383106102b1575050610313565b600183039250858311156102cf576102cc87878886036100a9565b96505b6102e3600985016003850160038501610079565b91506102f08782846100f7565b9650506103088461030386848601610186565b610186565b915050809350610200565b5050617fe061032884848589518601036100a9565b03925050506020820180820383525b81811161034e57617fe08101518152602001610337565b5060008152602001604052919050565b634e487b7160e01b600052604160045260246000fd5b60006020828403121561038657600080fd5b813567ffffffffffffffff8082111561039e57600080fd5b818401915084601f8301126103b257600080fd5b8135818111156103c4576103c461035e565b604051601f8201601f19908116603f011681019083821181831017156103ec576103ec61035e565b8160405282815287602084870101111561040557600080fd5b82602086016020830137600092810160200192909252509594505050505056fea264697066735822122000646b2953fc4a6f501bd0456ac52203089443937719e16b3190b7979c39511264736f6c63430008190033");
+
+    fn found(code: &[u8]) -> Vec<(usize, &'static str)> {
+        validate_runtime_code(code)
+            .iter()
+            .map(|v| (v.pc, v.name))
+            .collect()
+    }
+
+    #[test]
+    fn test_skips_solidity_metadata() {
+        assert!(found(&SOLC_RUNTIME).is_empty());
+
+        // The 51-byte map opens with a2 64 "ipfs" 58 22 and the hash's 12 20
+        // multihash prefix, then the digest
+        let map = SOLC_RUNTIME.len() - 2 - 51;
+        let digest = map + 10;
+
+        // The digest is arbitrary: a JUMPDEST and CALL byte in it are data
+        let mut code = SOLC_RUNTIME;
+        code[digest..digest + 2].copy_from_slice(&[0x5b, 0xf1]);
+        assert!(found(&code).is_empty());
+
+        // ...unless the trailer is not solc metadata, e.g. its key is renamed
+        code[map + 3] = b'X';
+        assert_eq!(found(&code), vec![(digest + 1, "CALL")]);
+    }
+
+    #[test]
+    fn test_forged_metadata_is_checked_as_code() {
+        // RETURN, then INVALID and a 4-byte "map" that is a JUMPDEST and CALL
+        assert_eq!(
+            found(&[0xf3, 0xfe, 0xa1, 0x5b, 0xf1, 0x00, 0x00, 0x04]),
+            vec![(4, "CALL")]
+        );
+
+        // Without the INVALID marker the tail is code too
+        assert_eq!(
+            found(&[0x5b, 0xa1, 0x5b, 0xf1, 0x00, 0x00, 0x04]),
+            vec![(1, "LOG1"), (3, "CALL")]
+        );
+    }
+}
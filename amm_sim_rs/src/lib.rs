@@ -10,109 +10,238 @@ pub mod market;
 pub mod simulation;
 pub mod types;
 
+use pyo3::create_exception;
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 
+use crate::evm::{CallbackTrace, OpcodeStep, RevertReason, StorageAccess, Violation};
+use crate::simulation::engine::SimulationError;
 use crate::simulation::runner::{
     run_simulations_parallel, run_simulations_parallel_v2, SimulationBatchConfig,
     SimulationBatchConfigV2,
 };
-use crate::types::config::{PoolConfigV2, SimulationConfig, SimulationConfigV2};
+use crate::types::config::{EvmConfig, PoolConfigV2, SimulationConfig, SimulationConfigV2};
 use crate::types::result::{
-    BatchSimulationResult, BatchSimulationResultV2, LightweightSimResult, LightweightSimResultV2,
-    PoolStateV2,
+    BatchSimulationResult, BatchSimulationResultV2, FailureCounts, GasStats, LightweightSimResult,
+    LightweightSimResultV2, PoolStateV2,
 };
 
-/// Run multiple simulations in parallel using Rust engine.
+/// Python entry points.
 ///
-/// # Arguments
-/// * `submission_bytecode` - Compiled bytecode for the submission strategy
-/// * `baseline_bytecode` - Compiled bytecode for the baseline strategy
-/// * `configs` - List of simulation configurations (one per simulation)
-/// * `n_workers` - Number of parallel workers (0 = auto-detect)
-///
-/// # Returns
-/// BatchSimulationResult containing all simulation results
-#[pyfunction]
-#[pyo3(signature = (submission_bytecode, baseline_bytecode, configs, n_workers = 0))]
-fn run_batch(
-    submission_bytecode: Vec<u8>,
-    baseline_bytecode: Vec<u8>,
-    configs: Vec<SimulationConfig>,
-    n_workers: usize,
-) -> PyResult<BatchSimulationResult> {
-    let batch_config = SimulationBatchConfig {
-        submission_bytecode,
-        baseline_bytecode,
-        configs,
-        n_workers: if n_workers == 0 {
-            None
-        } else {
-            Some(n_workers)
-        },
-    };
+/// pyo3 0.22's `#[pyfunction]` expansion converts `PyErr` into itself,
+/// which clippy flags in the generated wrappers.
+#[allow(clippy::useless_conversion)]
+mod functions {
+    use super::*;
+    use crate::evm::strategy::EVMStrategy;
+    use crate::simulation::engine::SimulationEngine;
 
-    run_simulations_parallel(batch_config)
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-}
+    /// Run multiple simulations in parallel using Rust engine.
+    ///
+    /// # Arguments
+    /// * `submission_bytecode` - Compiled bytecode for the submission strategy
+    /// * `baseline_bytecode` - Compiled bytecode for the baseline strategy
+    /// * `configs` - List of simulation configurations (one per simulation)
+    /// * `n_workers` - Number of parallel workers (0 = auto-detect)
+    ///
+    /// # Returns
+    /// BatchSimulationResult containing all simulation results
+    #[pyfunction]
+    #[pyo3(signature = (submission_bytecode, baseline_bytecode, configs, n_workers = 0))]
+    pub(super) fn run_batch(
+        submission_bytecode: Vec<u8>,
+        baseline_bytecode: Vec<u8>,
+        configs: Vec<SimulationConfig>,
+        n_workers: usize,
+    ) -> PyResult<BatchSimulationResult> {
+        let batch_config = SimulationBatchConfig {
+            submission_bytecode,
+            baseline_bytecode,
+            configs,
+            n_workers: if n_workers == 0 {
+                None
+            } else {
+                Some(n_workers)
+            },
+        };
 
-/// Run multiple multi-asset simulations in parallel using Rust engine.
-#[pyfunction]
-#[pyo3(signature = (submission_bytecode, baseline_bytecode, configs, n_workers = 0))]
-fn run_batch_v2(
-    submission_bytecode: Vec<u8>,
-    baseline_bytecode: Vec<u8>,
-    configs: Vec<SimulationConfigV2>,
-    n_workers: usize,
-) -> PyResult<BatchSimulationResultV2> {
-    let batch_config = SimulationBatchConfigV2 {
-        submission_bytecode,
-        baseline_bytecode,
-        configs,
-        n_workers: if n_workers == 0 {
-            None
-        } else {
-            Some(n_workers)
-        },
-    };
+        run_simulations_parallel(batch_config).map_err(to_py_err)
+    }
+
+    /// Run multiple multi-asset simulations in parallel using Rust engine.
+    #[pyfunction]
+    #[pyo3(signature = (submission_bytecode, baseline_bytecode, configs, n_workers = 0))]
+    pub(super) fn run_batch_v2(
+        submission_bytecode: Vec<u8>,
+        baseline_bytecode: Vec<u8>,
+        configs: Vec<SimulationConfigV2>,
+        n_workers: usize,
+    ) -> PyResult<BatchSimulationResultV2> {
+        let batch_config = SimulationBatchConfigV2 {
+            submission_bytecode,
+            baseline_bytecode,
+            configs,
+            n_workers: if n_workers == 0 {
+                None
+            } else {
+                Some(n_workers)
+            },
+        };
+
+        run_simulations_parallel_v2(batch_config).map_err(to_py_err)
+    }
 
-    run_simulations_parallel_v2(batch_config)
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+    /// Run a single simulation and return lightweight result.
+    ///
+    /// With `trace`, every committed strategy callback is traced at opcode level
+    /// and the traces are returned in `LightweightSimResult.traces`. Tracing is
+    /// slow and memory-hungry; keep `n_steps` small.
+    #[pyfunction]
+    #[pyo3(signature = (submission_bytecode, baseline_bytecode, config, trace = false))]
+    pub(super) fn run_single(
+        submission_bytecode: Vec<u8>,
+        baseline_bytecode: Vec<u8>,
+        config: SimulationConfig,
+        trace: bool,
+    ) -> PyResult<LightweightSimResult> {
+        let mut submission =
+            EVMStrategy::with_config(submission_bytecode, "Submission".to_string(), &config.evm)
+                .map_err(|e| to_py_err(SimulationError::from_evm("submission", e)))?;
+        let mut baseline =
+            EVMStrategy::with_config(baseline_bytecode, "Baseline".to_string(), &config.evm)
+                .map_err(|e| to_py_err(SimulationError::from_evm("normalizer", e)))?;
+        if trace {
+            submission.enable_tracing();
+            baseline.enable_tracing();
+        }
+
+        let mut engine = SimulationEngine::new(config);
+        engine.run(submission, baseline).map_err(to_py_err)
+    }
 }
 
-/// Run a single simulation and return lightweight result.
-#[pyfunction]
-fn run_single(
-    submission_bytecode: Vec<u8>,
-    baseline_bytecode: Vec<u8>,
-    config: SimulationConfig,
-) -> PyResult<LightweightSimResult> {
-    use crate::evm::strategy::EVMStrategy;
-    use crate::simulation::engine::SimulationEngine;
+create_exception!(
+    amm_sim_rs,
+    StrategyRevertError,
+    PyRuntimeError,
+    "A strategy call reverted.\n\nAttributes: `strategy` (\"submission\" or \"normalizer\"), \
+     `callback` (Solidity function name), `timestamp` (step, or None), `kind` \
+     (\"error\", \"panic\", \"custom\", \"empty\" or \"malformed\"), `reason` \
+     (human-readable), `message` (for `Error(string)`), `panic_code` (for \
+     `Panic(uint256)`) and `selector` (hex, for custom errors)."
+);
+
+create_exception!(
+    amm_sim_rs,
+    BytecodeValidationError,
+    PyRuntimeError,
+    "A strategy's runtime code contains forbidden instructions.\n\nAttributes: `strategy` \
+     (\"submission\" or \"normalizer\") and `violations`, a list of dicts with `pc`, \
+     `opcode`, `name` and `reason`."
+);
 
-    let submission = EVMStrategy::new(submission_bytecode, "Submission".to_string())
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
-    let baseline = EVMStrategy::new(baseline_bytecode, "Baseline".to_string())
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+/// Convert a simulation error into a Python exception.
+///
+/// Strategy reverts raise `StrategyRevertError` with the decoded reason
+/// attached, forbidden bytecode raises `BytecodeValidationError` with the
+/// violations attached; everything else raises `RuntimeError`.
+fn to_py_err(error: SimulationError) -> PyErr {
+    if let SimulationError::ForbiddenBytecode {
+        strategy,
+        violations,
+    } = &error
+    {
+        return bytecode_validation_err(&error, strategy, violations);
+    }
+    let SimulationError::StrategyReverted { strategy, revert } = &error else {
+        return PyRuntimeError::new_err(error.to_string());
+    };
+    let err = StrategyRevertError::new_err(error.to_string());
+    Python::with_gil(|py| {
+        let (message, panic_code, selector) = match &revert.reason {
+            RevertReason::Error(message) => (Some(message.clone()), None, None),
+            RevertReason::Panic(code) => (None, Some(*code), None),
+            RevertReason::Custom { selector, .. } => (
+                None,
+                None,
+                Some(format!("0x{:08x}", u32::from_be_bytes(*selector))),
+            ),
+            RevertReason::Empty | RevertReason::Malformed(_) => (None, None, None),
+        };
+        let value = err.value_bound(py);
+        let attributes = [
+            ("strategy", strategy.into_py(py)),
+            ("callback", revert.callback.into_py(py)),
+            ("timestamp", revert.timestamp.into_py(py)),
+            ("kind", revert.reason.kind().into_py(py)),
+            ("reason", revert.reason.to_string().into_py(py)),
+            ("message", message.into_py(py)),
+            ("panic_code", panic_code.into_py(py)),
+            ("selector", selector.into_py(py)),
+        ];
+        for (name, attribute) in attributes {
+            // Setting attributes on a fresh exception instance cannot fail
+            let _ = value.setattr(name, attribute);
+        }
+    });
+    err
+}
 
-    let mut engine = SimulationEngine::new(config);
-    engine
-        .run(submission, baseline)
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+/// Build a `BytecodeValidationError` carrying the violations.
+fn bytecode_validation_err(
+    error: &SimulationError,
+    strategy: &str,
+    violations: &[Violation],
+) -> PyErr {
+    let err = BytecodeValidationError::new_err(error.to_string());
+    Python::with_gil(|py| {
+        let violations: Vec<PyObject> = violations
+            .iter()
+            .map(|violation| {
+                let dict = PyDict::new_bound(py);
+                // Setting items on a fresh dict cannot fail
+                let _ = dict.set_item("pc", violation.pc);
+                let _ = dict.set_item("opcode", violation.opcode);
+                let _ = dict.set_item("name", violation.name);
+                let _ = dict.set_item("reason", violation.reason);
+                dict.into_py(py)
+            })
+            .collect();
+        let value = err.value_bound(py);
+        let _ = value.setattr("strategy", strategy);
+        let _ = value.setattr("violations", violations);
+    });
+    err
 }
 
 /// Python module definition
 #[pymodule]
 fn amm_sim_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(run_batch, m)?)?;
-    m.add_function(wrap_pyfunction!(run_batch_v2, m)?)?;
-    m.add_function(wrap_pyfunction!(run_single, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::run_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::run_batch_v2, m)?)?;
+    m.add_function(wrap_pyfunction!(functions::run_single, m)?)?;
     m.add_class::<SimulationConfig>()?;
     m.add_class::<PoolConfigV2>()?;
+    m.add_class::<EvmConfig>()?;
     m.add_class::<SimulationConfigV2>()?;
     m.add_class::<LightweightSimResult>()?;
     m.add_class::<BatchSimulationResult>()?;
     m.add_class::<PoolStateV2>()?;
     m.add_class::<LightweightSimResultV2>()?;
     m.add_class::<BatchSimulationResultV2>()?;
+    m.add_class::<GasStats>()?;
+    m.add_class::<FailureCounts>()?;
+    m.add(
+        "StrategyRevertError",
+        m.py().get_type_bound::<StrategyRevertError>(),
+    )?;
+    m.add(
+        "BytecodeValidationError",
+        m.py().get_type_bound::<BytecodeValidationError>(),
+    )?;
+    m.add_class::<CallbackTrace>()?;
+    m.add_class::<OpcodeStep>()?;
+    m.add_class::<StorageAccess>()?;
     Ok(())
 }
@@ -1,6 +1,9 @@
 //! Arbitrageur logic for extracting profit from mispriced AMMs.
 
-use crate::amm::CFMM;
+use crate::amm::{CurveKind, CFMM};
+
+/// Golden-section iterations for numerically sized trades.
+const SEARCH_ITERATIONS: usize = 100;
 
 /// Result of an arbitrage attempt.
 #[derive(Debug, Clone)]
@@ -19,10 +22,20 @@ pub struct ArbResult {
 
 /// Arbitrageur that extracts profit from mispriced AMMs.
 ///
-/// Uses closed-form solutions for constant product AMMs.
-/// For reserves (x, y), k=xy, fee f (fee-on-input), γ = 1 - f, and fair price p (Y per X):
-/// - Buy X from AMM (AMM sells X): Δx_out = x - sqrt(k / (γ·p)) (profit-maximizing)
-/// - Sell X to AMM (AMM buys X): Δx_in = (sqrt(k·γ / p) - x) / γ (profit-maximizing, Δx_in is gross input)
+/// Trades each pool until its fee-adjusted marginal price reaches the fair
+/// price, using the pool curve's `reserve_x_at_price` to find the target
+/// reserve. For fee f (fee-on-input), γ = 1 - f, and fair price p (Y per X):
+/// - Buy X from AMM (AMM sells X): Δx_out = x - x*(γ·p)
+/// - Sell X to AMM (AMM buys X): Δx_in = (x*(p/γ) - x) / γ (Δx_in is gross input)
+///
+/// where x*(q) is the X reserve at which the curve's spot price equals q.
+/// Closed forms:
+/// - Constant product (k=xy): x*(q) = sqrt(k / q)
+/// - Weighted product (x^w·y^(1-w)=k, spot s): x*(q) = x·(s / q)^(1-w)
+///
+/// Curves without a closed form (StableSwap, custom curves), and pools whose
+/// strategy prices each trade in a `beforeSwap` hook, are sized numerically
+/// by maximizing the quoted profit with a golden-section search.
 pub struct Arbitrageur;
 
 impl Arbitrageur {
@@ -38,8 +51,7 @@ impl Arbitrageur {
         fair_price: f64,
         timestamp: u64,
     ) -> Option<ArbResult> {
-        let (rx, ry) = amm.reserves();
-        let spot_price = ry / rx;
+        let spot_price = amm.spot_price();
 
         if spot_price < fair_price {
             // AMM underprices X - buy X from AMM (AMM sells X)
@@ -55,7 +67,8 @@ impl Arbitrageur {
     /// Compute and execute optimal trade when buying X from AMM.
     ///
     /// Maximize profit = Δx * p - Y_paid
-    /// Closed-form (fee-on-input): Δx_out = x - sqrt(k / (γ·p))
+    /// Optimum (fee-on-input): Δx_out = x - x*(γ·p)
+    /// Optimum (fee-on-output): Δx_out = γ·(x - x*(γ·p))
     fn compute_buy_arb(
         &self,
         amm: &mut CFMM,
@@ -63,7 +76,6 @@ impl Arbitrageur {
         timestamp: u64,
     ) -> Option<ArbResult> {
         let (rx, ry) = amm.reserves();
-        let k = rx * ry;
         let fee = amm.fees().ask_fee.to_f64();
         let gamma = 1.0 - fee;
 
@@ -72,10 +84,25 @@ impl Arbitrageur {
         }
 
         // Optimal trade size
-        let new_x = (k / (gamma * fair_price)).sqrt();
-        let amount_x = rx - new_x;
-
-        if amount_x <= 0.0 {
+        let amount_x = if has_closed_form(amm) {
+            let gross_x = rx - amm.curve().reserve_x_at_price(rx, ry, gamma * fair_price);
+            if amm.fee_mode().fee_on_input() {
+                gross_x
+            } else {
+                gross_x * gamma
+            }
+        } else {
+            let profit_at = |amount_x: f64| {
+                let (total_y, _) = amm.quote_sell_x(amount_x);
+                if total_y <= 0.0 {
+                    return f64::NEG_INFINITY;
+                }
+                amount_x * fair_price - total_y
+            };
+            golden_section_max(profit_at, 0.0, rx * 0.99)
+        };
+
+        if amount_x <= 0.0 || !amount_x.is_finite() {
             return None;
         }
 
@@ -95,12 +122,14 @@ impl Arbitrageur {
             return None;
         }
 
-        // Execute the trade
-        let _trade = amm.execute_sell_x(amount_x, timestamp)?;
+        // Execute the trade; the executed fee may differ from the quote
+        // when the strategy prices trades in beforeSwap
+        let trade = amm.execute_sell_x(amount_x, timestamp)?;
+        let total_y = trade.trade_info.amount_y.to_f64();
 
         Some(ArbResult {
             amm_name: amm.name.clone(),
-            profit,
+            profit: amount_x * fair_price - total_y,
             side: "sell", // AMM sells X
             amount_x,
             amount_y: total_y,
@@ -110,7 +139,8 @@ impl Arbitrageur {
     /// Compute and execute optimal trade when selling X to AMM.
     ///
     /// Maximize profit = Y_received - Δx * p
-    /// Closed-form (fee-on-input): Δx_in = (sqrt(k·γ / p) - x) / γ
+    /// Optimum (fee-on-input): Δx_in = (x*(p/γ) - x) / γ
+    /// Optimum (fee-on-output): Δx_in = x*(p/γ) - x
     fn compute_sell_arb(
         &self,
         amm: &mut CFMM,
@@ -118,7 +148,6 @@ impl Arbitrageur {
         timestamp: u64,
     ) -> Option<ArbResult> {
         let (rx, ry) = amm.reserves();
-        let k = rx * ry;
         let fee = amm.fees().bid_fee.to_f64();
         let gamma = 1.0 - fee;
 
@@ -127,12 +156,32 @@ impl Arbitrageur {
         }
 
         // Optimal trade size (gross input):
-        // x + γ·Δx_in = sqrt(k·γ/p)  =>  Δx_in = (sqrt(k·γ/p) - x) / γ
-        let x_virtual = (k * gamma / fair_price).sqrt();
-        let net_x = x_virtual - rx;
-        let amount_x = net_x / gamma;
-
-        if amount_x <= 0.0 {
+        // x + γ·Δx_in = x*(p/γ)  =>  Δx_in = (x*(p/γ) - x) / γ
+        let amount_x = if has_closed_form(amm) {
+            let x_virtual = amm.curve().reserve_x_at_price(rx, ry, fair_price / gamma);
+            let net_x = x_virtual - rx;
+            if amm.fee_mode().fee_on_input() {
+                net_x / gamma
+            } else {
+                net_x
+            }
+        } else {
+            let profit_at = |amount_x: f64| {
+                let (y_out, _) = amm.quote_buy_x(amount_x);
+                if y_out <= 0.0 {
+                    return f64::NEG_INFINITY;
+                }
+                y_out - amount_x * fair_price
+            };
+            // Profit is concave in size; grow the bracket until it turns down.
+            let mut hi = rx;
+            while profit_at(2.0 * hi) > profit_at(hi) && hi.is_finite() {
+                hi *= 2.0;
+            }
+            golden_section_max(profit_at, 0.0, 2.0 * hi)
+        };
+
+        if amount_x <= 0.0 || !amount_x.is_finite() {
             return None;
         }
 
@@ -149,12 +198,14 @@ impl Arbitrageur {
             return None;
         }
 
-        // Execute the trade
-        let _trade = amm.execute_buy_x(amount_x, timestamp)?;
+        // Execute the trade; the executed fee may differ from the quote
+        // when the strategy prices trades in beforeSwap
+        let trade = amm.execute_buy_x(amount_x, timestamp)?;
+        let y_out = trade.trade_info.amount_y.to_f64();
 
         Some(ArbResult {
             amm_name: amm.name.clone(),
-            profit,
+            profit: y_out - amount_x * fair_price,
             side: "buy", // AMM buys X
             amount_x,
             amount_y: y_out,
@@ -174,6 +225,43 @@ impl Arbitrageur {
     }
 }
 
+/// Whether the pool's curve has a closed-form target reserve at a fixed fee.
+fn has_closed_form(amm: &CFMM) -> bool {
+    !amm.has_before_swap()
+        && matches!(
+            amm.curve().kind(),
+            CurveKind::ConstantProduct
+                | CurveKind::Weighted { .. }
+                | CurveKind::Concentrated
+                | CurveKind::Pmm { .. }
+        )
+}
+
+/// Maximize a unimodal function on [lo, hi] by golden-section search.
+fn golden_section_max<F: Fn(f64) -> f64>(f: F, mut lo: f64, mut hi: f64) -> f64 {
+    let inv_phi = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let mut a = hi - inv_phi * (hi - lo);
+    let mut b = lo + inv_phi * (hi - lo);
+    let mut fa = f(a);
+    let mut fb = f(b);
+    for _ in 0..SEARCH_ITERATIONS {
+        if fa < fb {
+            lo = a;
+            a = b;
+            fa = fb;
+            b = lo + inv_phi * (hi - lo);
+            fb = f(b);
+        } else {
+            hi = b;
+            b = a;
+            fb = fa;
+            a = hi - inv_phi * (hi - lo);
+            fa = f(a);
+        }
+    }
+    0.5 * (lo + hi)
+}
+
 impl Default for Arbitrageur {
     fn default() -> Self {
         Self::new()
@@ -182,8 +270,11 @@ impl Default for Arbitrageur {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
+    use super::{golden_section_max, Arbitrageur};
+    use crate::amm::{ConcentratedLiquidity, FeeMode, PricingCurve, StableSwap, CFMM};
+    use crate::evm::test_support::FIXED_FEE_INIT;
+    use crate::evm::EVMStrategy;
+    use crate::types::wad::Wad;
     fn quote_buy_x(reserve_x: f64, reserve_y: f64, fee: f64, amount_x_in: f64) -> f64 {
         if amount_x_in <= 0.0 {
             return 0.0;
@@ -219,7 +310,7 @@ mod tests {
     #[test]
     fn test_arb_formulas() {
         // Test the closed-form formulas without EVM
-        let rx = 1000.0;
+        let rx: f64 = 1000.0;
         let ry = 1000.0;
         let k = rx * ry;
         let fee = 0.0025; // 25 bps
@@ -240,7 +331,7 @@ mod tests {
 
     #[test]
     fn test_arb_sizes_maximize_profit() {
-        let rx = 1000.0;
+        let rx: f64 = 1000.0;
         let ry = 1000.0;
         let k = rx * ry;
         let fee = 0.05; // 5%
@@ -278,7 +369,7 @@ mod tests {
 
     #[test]
     fn test_arb_moves_price_into_no_arb_band() {
-        let rx = 1000.0;
+        let rx: f64 = 1000.0;
         let ry = 1000.0;
         let fee = 0.05; // 5%
         let gamma = 1.0 - fee;
@@ -304,4 +395,79 @@ mod tests {
         let spot2 = ry2 / rx2;
         assert!(spot2 <= fair_price / gamma + 1e-9);
     }
+
+    #[test]
+    fn test_numeric_size_matches_marginal_condition() {
+        // StableSwap has no closed form: the numerically optimal size should
+        // bring the post-trade spot price to γ·p, same as the marginal condition.
+        let curve = StableSwap::new(50.0, 1.0).unwrap();
+        let (rx, ry) = (1000.0, 1000.0);
+        let fee: f64 = 0.003;
+        let gamma = 1.0 - fee;
+        let fair_price = 1.01;
+
+        let profit_at = |amount_x: f64| {
+            let total_y = curve.y_in_for_x_out(rx, ry, amount_x) / gamma;
+            amount_x * fair_price - total_y
+        };
+        let amount_x = golden_section_max(profit_at, 0.0, rx * 0.99);
+
+        let target_x = curve.reserve_x_at_price(rx, ry, gamma * fair_price);
+        let marginal_size = rx - target_x;
+        assert!((amount_x - marginal_size).abs() / marginal_size < 1e-6);
+    }
+
+    #[test]
+    fn test_arb_drains_concentrated_pool_outside_range() {
+        // Fair price below the range: the optimal sell pushes the pool to the
+        // lower bound, leaving it holding only X.
+        let curve = ConcentratedLiquidity::new(&[(0.8, 1.25, 1.0)]).unwrap();
+        let (rx, ry) = (1000.0, 1000.0);
+        let gamma = 1.0 - 0.003;
+        let fair_price = 0.5;
+
+        let x_target = curve.reserve_x_at_price(rx, ry, fair_price / gamma);
+        let amount_x = (x_target - rx) / gamma;
+        let y_out = curve.y_out_for_x_in(rx, ry, gamma * amount_x);
+        assert!((y_out - ry).abs() < 1e-6);
+        assert!((curve.spot_price(rx + gamma * amount_x, ry - y_out) - 0.8).abs() < 1e-9);
+    }
+
+    /// A constant product pool charging a fixed 5% fee on output.
+    fn fee_on_output_pool() -> CFMM {
+        let strategy = EVMStrategy::new(FIXED_FEE_INIT.to_vec(), "fixed".into()).unwrap();
+        let mut amm = CFMM::new(strategy, 1000.0, 1000.0);
+        amm.set_fee_mode(FeeMode::Output);
+        amm.initialize().unwrap();
+        assert_eq!(amm.fees().bid_fee, Wad::from_bps(500));
+        amm
+    }
+
+    #[test]
+    fn test_fee_on_output_sizes_maximize_profit() {
+        let arbitrageur = Arbitrageur::new();
+        // Above spot the AMM sells X, below it buys X
+        for (fair_price, amm_sells_x) in [(1.2, true), (0.9, false)] {
+            let arb = arbitrageur
+                .execute_arb(&mut fee_on_output_pool(), fair_price, 0)
+                .unwrap();
+            assert_eq!(arb.side, if amm_sells_x { "sell" } else { "buy" });
+
+            // Profit of trading `amount_x` against a fresh pool
+            let realized = |amount_x: f64| {
+                let mut amm = fee_on_output_pool();
+                if amm_sells_x {
+                    let trade = amm.execute_sell_x(amount_x, 0).unwrap();
+                    amount_x * fair_price - trade.trade_info.amount_y.to_f64()
+                } else {
+                    let trade = amm.execute_buy_x(amount_x, 0).unwrap();
+                    trade.trade_info.amount_y.to_f64() - amount_x * fair_price
+                }
+            };
+            assert!((realized(arb.amount_x) - arb.profit).abs() < 1e-9);
+            for amount_x in [arb.amount_x * 0.99, arb.amount_x * 1.01] {
+                assert!(realized(amount_x) < arb.profit);
+            }
+        }
+    }
 }
@@ -0,0 +1,122 @@
+//! Liquidity provider flow: scheduled and stochastic deposits and withdrawals.
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand_distr::{Distribution, Exp, Poisson};
+use rand_pcg::Pcg64;
+
+/// Largest share of the pool a single stochastic withdrawal may take.
+const MAX_WITHDRAW_FRACTION: f64 = 0.5;
+
+/// Generates LP deposits and withdrawals for one pool.
+///
+/// Each event is a fraction of the current LP share supply: positive to
+/// deposit, negative to withdraw. Scheduled events fire at fixed steps.
+/// Stochastic events arrive as a Poisson process with exponentially
+/// distributed sizes; their direction leans towards deposits when the
+/// pool's per-share return is positive and towards withdrawals when it is
+/// negative, so liquidity follows performance.
+pub struct LiquidityFlow {
+    /// (step, fraction of supply) events, sorted by step
+    schedule: Vec<(u32, f64)>,
+    /// Mean stochastic event size (fraction of supply)
+    mean_size: f64,
+    /// How strongly the deposit probability follows per-share return
+    sensitivity: f64,
+    /// Random number generator
+    rng: Pcg64,
+    /// Arrivals per step; None disables stochastic flow
+    poisson: Option<Poisson<f64>>,
+}
+
+impl LiquidityFlow {
+    /// Create a new LP flow. An `arrival_rate` of 0 gives scheduled events only.
+    pub fn new(
+        schedule: Vec<(u32, f64)>,
+        arrival_rate: f64,
+        mean_size: f64,
+        sensitivity: f64,
+        seed: Option<u64>,
+    ) -> Self {
+        let rng = match seed {
+            Some(s) => Pcg64::seed_from_u64(s),
+            None => Pcg64::from_entropy(),
+        };
+        let mut schedule = schedule;
+        schedule.sort_by_key(|(step, _)| *step);
+        let poisson = if arrival_rate > 0.0 {
+            Poisson::new(arrival_rate).ok()
+        } else {
+            None
+        };
+
+        Self {
+            schedule,
+            mean_size: mean_size.max(0.0),
+            sensitivity,
+            rng,
+            poisson,
+        }
+    }
+
+    /// Probability that a stochastic event is a deposit.
+    #[inline]
+    fn deposit_prob(&self, share_return: f64) -> f64 {
+        1.0 / (1.0 + (-self.sensitivity * share_return).exp())
+    }
+
+    /// Events for `step`, given the pool's per-share return so far.
+    pub fn events(&mut self, step: u32, share_return: f64) -> Vec<f64> {
+        let mut events: Vec<f64> = self
+            .schedule
+            .iter()
+            .filter(|(s, _)| *s == step)
+            .map(|(_, fraction)| *fraction)
+            .collect();
+
+        let Some(poisson) = self.poisson else {
+            return events;
+        };
+        let n_arrivals = poisson.sample(&mut self.rng) as usize;
+        if n_arrivals == 0 || self.mean_size <= 0.0 {
+            return events;
+        }
+
+        let size_dist = Exp::new(1.0 / self.mean_size).expect("mean size is positive");
+        let deposit_prob = self.deposit_prob(share_return);
+        for _ in 0..n_arrivals {
+            let size = size_dist.sample(&mut self.rng);
+            if self.rng.gen::<f64>() < deposit_prob {
+                events.push(size);
+            } else {
+                events.push(-size.min(MAX_WITHDRAW_FRACTION));
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_fires_on_its_step() {
+        let mut flow = LiquidityFlow::new(vec![(5, 0.2), (2, -0.1)], 0.0, 0.05, 0.0, Some(1));
+        assert!(flow.events(0, 0.0).is_empty());
+        assert_eq!(flow.events(2, 0.0), vec![-0.1]);
+        assert_eq!(flow.events(5, 0.0), vec![0.2]);
+    }
+
+    #[test]
+    fn test_flow_follows_performance() {
+        let count_deposits = |share_return: f64| {
+            let mut flow = LiquidityFlow::new(Vec::new(), 2.0, 0.05, 50.0, Some(9));
+            (0..500)
+                .flat_map(|t| flow.events(t, share_return))
+                .filter(|f| *f > 0.0)
+                .count()
+        };
+        assert!(count_deposits(0.05) > count_deposits(-0.05));
+    }
+}
@@ -1,6 +1,8 @@
 //! Market actors and price processes.
 
 pub mod arbitrageur;
+pub mod liquidity;
+pub mod oracle;
 pub mod price_process;
 pub mod price_process_v2;
 pub mod retail;
@@ -8,6 +10,8 @@ pub mod retail_v2;
 pub mod router;
 
 pub use arbitrageur::Arbitrageur;
+pub use liquidity::LiquidityFlow;
+pub use oracle::OracleFeed;
 pub use price_process::GBMPriceProcess;
 pub use price_process_v2::MultiAssetPriceProcess;
 pub use retail::{RetailOrder, RetailTrader};
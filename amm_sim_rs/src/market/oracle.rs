@@ -0,0 +1,77 @@
+//! Push oracle feed published to strategies.
+
+/// Chainlink-style price feed.
+///
+/// Follows a source price (typically the lagged, noisy oracle of a price
+/// process) but only publishes a new round when the heartbeat has elapsed
+/// or the source has moved by at least the deviation threshold since the
+/// last published price. Between rounds strategies see a stale price.
+pub struct OracleFeed {
+    /// Steps after which a round is published regardless of movement (0 = every step)
+    heartbeat: u32,
+    /// Relative move that triggers an early round (0 = every step)
+    deviation: f64,
+    /// Last published price
+    price: f64,
+    /// Step of the last published round
+    updated_at: u64,
+}
+
+impl OracleFeed {
+    /// Create a feed whose first round is `initial_price` at step 0.
+    pub fn new(heartbeat: u32, deviation: f64, initial_price: f64) -> Self {
+        Self {
+            heartbeat,
+            deviation: deviation.max(0.0),
+            price: initial_price,
+            updated_at: 0,
+        }
+    }
+
+    /// Observe the source price at `step`. Returns true if a new round was published.
+    pub fn observe(&mut self, step: u64, source_price: f64) -> bool {
+        let stale = step.saturating_sub(self.updated_at) >= self.heartbeat as u64;
+        let moved = (source_price / self.price - 1.0).abs() >= self.deviation;
+        if !(stale || moved) {
+            return false;
+        }
+        self.price = source_price;
+        self.updated_at = step;
+        true
+    }
+
+    /// Last published price.
+    #[inline]
+    pub fn price(&self) -> f64 {
+        self.price
+    }
+
+    /// Step of the last published round.
+    #[inline]
+    pub fn updated_at(&self) -> u64 {
+        self.updated_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_publishes_stale_price() {
+        let mut feed = OracleFeed::new(3, 1.0, 100.0);
+        assert!(!feed.observe(1, 101.0));
+        assert!(!feed.observe(2, 102.0));
+        assert_eq!(feed.price(), 100.0);
+        assert!(feed.observe(3, 103.0));
+        assert_eq!((feed.price(), feed.updated_at()), (103.0, 3));
+    }
+
+    #[test]
+    fn test_deviation_triggers_early_round() {
+        let mut feed = OracleFeed::new(100, 0.01, 100.0);
+        assert!(!feed.observe(1, 100.5));
+        assert!(feed.observe(2, 98.9));
+        assert_eq!((feed.price(), feed.updated_at()), (98.9, 2));
+    }
+}
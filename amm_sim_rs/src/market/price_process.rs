@@ -1,5 +1,7 @@
 //! Geometric Brownian Motion price process.
 
+use std::collections::VecDeque;
+
 use rand::SeedableRng;
 use rand_distr::{Distribution, StandardNormal};
 use rand_pcg::Pcg64;
@@ -30,6 +32,19 @@ pub struct GBMPriceProcess {
     vol_term: f64,
     /// Random number generator
     rng: Pcg64,
+    /// Oracle delay in steps
+    oracle_lag: usize,
+    /// Lognormal oracle noise (log-space sigma)
+    oracle_noise: f64,
+    /// Recent fair prices, oldest first (at most oracle_lag + 1)
+    history: VecDeque<f64>,
+    /// Current oracle price
+    oracle_price: f64,
+    /// Separate generator for oracle noise, so the fair price path does not
+    /// depend on the oracle settings
+    oracle_rng: Pcg64,
+    /// Seed of `oracle_rng`, restored on reset
+    oracle_seed: Option<u64>,
 }
 
 impl GBMPriceProcess {
@@ -48,9 +63,39 @@ impl GBMPriceProcess {
             drift_term: (mu - 0.5 * sigma * sigma) * dt,
             vol_term: sigma * dt.sqrt(),
             rng,
+            oracle_lag: 0,
+            oracle_noise: 0.0,
+            history: VecDeque::from([initial_price]),
+            oracle_price: initial_price,
+            oracle_rng: Pcg64::seed_from_u64(0),
+            oracle_seed: Some(0),
         }
     }
 
+    /// Configure the oracle feed: the fair price `lag` steps ago, multiplied
+    /// by lognormal noise with log-space sigma `noise`.
+    pub fn with_oracle(mut self, lag: usize, noise: f64, seed: Option<u64>) -> Self {
+        self.oracle_lag = lag;
+        self.oracle_noise = noise;
+        self.oracle_seed = seed;
+        self.reseed_oracle();
+        self
+    }
+
+    /// Restart the oracle noise generator from its seed.
+    fn reseed_oracle(&mut self) {
+        self.oracle_rng = match self.oracle_seed {
+            Some(s) => Pcg64::seed_from_u64(s),
+            None => Pcg64::from_entropy(),
+        };
+    }
+
+    /// Get current oracle price.
+    #[inline]
+    pub fn oracle_price(&self) -> f64 {
+        self.oracle_price
+    }
+
     /// Get current price.
     #[inline]
     pub fn current_price(&self) -> f64 {
@@ -63,15 +108,38 @@ impl GBMPriceProcess {
         let z: f64 = StandardNormal.sample(&mut self.rng);
         let exponent = self.drift_term + self.vol_term * z;
         self.current_price *= exponent.exp();
+        self.update_oracle();
         self.current_price
     }
 
+    /// Record the current price and refresh the oracle.
+    fn update_oracle(&mut self) {
+        self.history.push_back(self.current_price);
+        while self.history.len() > self.oracle_lag + 1 {
+            self.history.pop_front();
+        }
+        let lagged = self.history[0];
+        self.oracle_price = if self.oracle_noise > 0.0 {
+            let z: f64 = StandardNormal.sample(&mut self.oracle_rng);
+            lagged * (self.oracle_noise * z).exp()
+        } else {
+            lagged
+        };
+    }
+
     /// Reset the price process.
+    ///
+    /// `seed` reseeds the price path; the oracle noise restarts from the
+    /// seed given to `with_oracle`, so a reset run repeats its oracle too.
     pub fn reset(&mut self, initial_price: f64, seed: Option<u64>) {
         self.current_price = initial_price;
+        self.history.clear();
+        self.history.push_back(initial_price);
+        self.oracle_price = initial_price;
         if let Some(s) = seed {
             self.rng = Pcg64::seed_from_u64(s);
         }
+        self.reseed_oracle();
     }
 }
 
@@ -100,4 +168,42 @@ mod tests {
             assert!(price > 0.0);
         }
     }
+
+    #[test]
+    fn test_oracle_lags_fair_price() {
+        let mut process =
+            GBMPriceProcess::new(100.0, 0.0, 0.1, 1.0, Some(42)).with_oracle(3, 0.0, None);
+        let mut prices = vec![100.0];
+        for _ in 0..10 {
+            prices.push(process.step());
+            let t = prices.len() - 1;
+            assert_eq!(process.oracle_price(), prices[t.saturating_sub(3)]);
+        }
+    }
+
+    #[test]
+    fn test_oracle_noise_keeps_fair_path() {
+        let mut plain = GBMPriceProcess::new(100.0, 0.0, 0.1, 1.0, Some(42));
+        let mut noisy =
+            GBMPriceProcess::new(100.0, 0.0, 0.1, 1.0, Some(42)).with_oracle(0, 0.01, Some(7));
+        for _ in 0..100 {
+            assert_eq!(plain.step(), noisy.step());
+            assert!(noisy.oracle_price() > 0.0);
+        }
+        assert_ne!(noisy.oracle_price(), noisy.current_price());
+    }
+
+    #[test]
+    fn test_reset_repeats_oracle_noise() {
+        let mut process =
+            GBMPriceProcess::new(100.0, 0.0, 0.1, 1.0, Some(42)).with_oracle(2, 0.01, Some(7));
+        let run = |process: &mut GBMPriceProcess| {
+            (0..20)
+                .map(|_| (process.step(), process.oracle_price()))
+                .collect::<Vec<_>>()
+        };
+        let first = run(&mut process);
+        process.reset(100.0, Some(42));
+        assert_eq!(run(&mut process), first);
+    }
 }
@@ -1,5 +1,7 @@
 //! Multi-asset geometric Brownian motion price process.
 
+use std::collections::VecDeque;
+
 use rand::SeedableRng;
 use rand_distr::{Distribution, StandardNormal};
 use rand_pcg::Pcg64;
@@ -8,12 +10,23 @@ use rand_pcg::Pcg64;
 ///
 /// Each non-numeraire asset follows independent GBM:
 /// S(t+1) = S(t) * exp((mu - 0.5*sigma^2)*dt + sigma*sqrt(dt)*Z)
+///
+/// An optional oracle reports each price `oracle_lag` steps late with
+/// lognormal noise, like the single-asset `GBMPriceProcess` oracle.
 pub struct MultiAssetPriceProcess {
     prices: Vec<f64>,
     numeraire_token: usize,
     drift_term: f64,
     vol_term: f64,
     rng: Pcg64,
+    oracle_lag: usize,
+    oracle_noise: f64,
+    /// Recent price vectors, oldest first (at most oracle_lag + 1)
+    history: VecDeque<Vec<f64>>,
+    oracle_prices: Vec<f64>,
+    /// Separate generator for oracle noise, so the fair price path does not
+    /// depend on the oracle settings
+    oracle_rng: Pcg64,
 }
 
 impl MultiAssetPriceProcess {
@@ -36,18 +49,40 @@ impl MultiAssetPriceProcess {
         }
 
         Self {
+            history: VecDeque::from([prices.clone()]),
+            oracle_prices: prices.clone(),
             prices,
             numeraire_token,
             drift_term: (mu - 0.5 * sigma * sigma) * dt,
             vol_term: sigma * dt.sqrt(),
             rng,
+            oracle_lag: 0,
+            oracle_noise: 0.0,
+            oracle_rng: Pcg64::seed_from_u64(0),
         }
     }
 
+    /// Configure the oracle feed: prices `lag` steps ago, multiplied by
+    /// lognormal noise with log-space sigma `noise`.
+    pub fn with_oracle(mut self, lag: usize, noise: f64, seed: Option<u64>) -> Self {
+        self.oracle_lag = lag;
+        self.oracle_noise = noise;
+        self.oracle_rng = match seed {
+            Some(s) => Pcg64::seed_from_u64(s),
+            None => Pcg64::from_entropy(),
+        };
+        self
+    }
+
     pub fn current_prices(&self) -> &[f64] {
         &self.prices
     }
 
+    /// Current oracle prices in numeraire terms (the numeraire stays at 1).
+    pub fn oracle_prices(&self) -> &[f64] {
+        &self.oracle_prices
+    }
+
     pub fn step(&mut self) -> &[f64] {
         for (idx, p) in self.prices.iter_mut().enumerate() {
             if idx == self.numeraire_token {
@@ -60,8 +95,30 @@ impl MultiAssetPriceProcess {
                 *p = 1e-9;
             }
         }
+        self.update_oracle();
         &self.prices
     }
+
+    /// Record the current prices and refresh the oracle.
+    fn update_oracle(&mut self) {
+        self.history.push_back(self.prices.clone());
+        while self.history.len() > self.oracle_lag + 1 {
+            self.history.pop_front();
+        }
+        for (idx, (oracle, lagged)) in self
+            .oracle_prices
+            .iter_mut()
+            .zip(self.history[0].iter())
+            .enumerate()
+        {
+            *oracle = if self.oracle_noise > 0.0 && idx != self.numeraire_token {
+                let z: f64 = StandardNormal.sample(&mut self.oracle_rng);
+                lagged * (self.oracle_noise * z).exp()
+            } else {
+                *lagged
+            };
+        }
+    }
 }
 
 #[cfg(test)]
@@ -86,4 +143,13 @@ mod tests {
             assert_eq!(p.current_prices()[0], 1.0);
         }
     }
+
+    #[test]
+    fn test_oracle_lags_prices() {
+        let mut p = MultiAssetPriceProcess::new(vec![2.0, 1.0], 1, 0.0, 0.1, 1.0, Some(3))
+            .with_oracle(2, 0.0, None);
+        let path: Vec<f64> = (0..6).map(|_| p.step()[0]).collect();
+        assert_eq!(p.oracle_prices()[0], path[3]);
+        assert_eq!(p.oracle_prices()[1], 1.0);
+    }
 }
@@ -11,6 +11,8 @@ pub struct RetailOrder {
     pub side: &'static str,
     /// Size in Y terms (how much Y willing to spend/receive)
     pub size: f64,
+    /// Fix the amount received rather than the amount paid
+    pub exact_out: bool,
 }
 
 /// Generates retail trading flow with Poisson arrivals.
@@ -30,6 +32,8 @@ pub struct RetailTrader {
     size_sigma: f64,
     /// Probability of a buy order
     buy_prob: f64,
+    /// Probability that an order is exact-output
+    exact_out_prob: f64,
     /// Random number generator
     rng: Pcg64,
     /// Poisson distribution for arrivals
@@ -66,12 +70,22 @@ impl RetailTrader {
             mean_size,
             size_sigma: sigma,
             buy_prob,
+            exact_out_prob: 0.0,
             rng,
             poisson,
             lognormal,
         }
     }
 
+    /// Emit a fraction `prob` of orders as exact-output.
+    ///
+    /// A buy then fixes the X received (worth `size` at the fair price) and
+    /// a sell fixes the Y received (`size`).
+    pub fn with_exact_out_prob(mut self, prob: f64) -> Self {
+        self.exact_out_prob = prob.clamp(0.0, 1.0);
+        self
+    }
+
     /// Generate retail orders for one time step.
     #[inline]
     pub fn generate_orders(&mut self) -> Vec<RetailOrder> {
@@ -95,7 +109,15 @@ impl RetailTrader {
                 "sell"
             };
 
-            orders.push(RetailOrder { side, size });
+            // Only draw when enabled so exact-input flow keeps its random stream
+            let exact_out = self.exact_out_prob > 0.0
+                && rand::Rng::gen::<f64>(&mut self.rng) < self.exact_out_prob;
+
+            orders.push(RetailOrder {
+                side,
+                size,
+                exact_out,
+            });
         }
 
         orders
@@ -141,4 +163,15 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_exact_out_prob() {
+        let mut plain = RetailTrader::new(5.0, 2.0, 0.5, 0.5, Some(42));
+        let mut all = RetailTrader::new(5.0, 2.0, 0.5, 0.5, Some(42)).with_exact_out_prob(1.0);
+
+        for _ in 0..20 {
+            assert!(plain.generate_orders().iter().all(|o| !o.exact_out));
+            assert!(all.generate_orders().iter().all(|o| o.exact_out));
+        }
+    }
 }
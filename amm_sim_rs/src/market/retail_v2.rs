@@ -12,6 +12,8 @@ pub struct RetailOrderV2 {
     pub token_out: usize,
     /// Order notional measured in numeraire terms.
     pub size_numeraire: f64,
+    /// Notional fixes the token_out received rather than the token_in paid.
+    pub exact_out: bool,
 }
 
 /// Generates retail flow across a token universe.
@@ -22,6 +24,7 @@ pub struct RetailOrderV2 {
 pub struct RetailTraderV2 {
     n_assets: usize,
     buy_prob: f64,
+    exact_out_prob: f64,
     rng: Pcg64,
     poisson: Poisson<f64>,
     lognormal: LogNormal<f64>,
@@ -49,12 +52,19 @@ impl RetailTraderV2 {
         Self {
             n_assets,
             buy_prob,
+            exact_out_prob: 0.0,
             rng,
             poisson,
             lognormal,
         }
     }
 
+    /// Emit a fraction `prob` of orders as exact-output.
+    pub fn with_exact_out_prob(mut self, prob: f64) -> Self {
+        self.exact_out_prob = prob.clamp(0.0, 1.0);
+        self
+    }
+
     pub fn generate_orders(&mut self) -> Vec<RetailOrderV2> {
         let n_arrivals = self.poisson.sample(&mut self.rng) as usize;
         if n_arrivals == 0 {
@@ -73,10 +83,13 @@ impl RetailTraderV2 {
             } else {
                 (a, b)
             };
+            let size_numeraire = self.lognormal.sample(&mut self.rng);
+            let exact_out = self.exact_out_prob > 0.0 && self.rng.gen::<f64>() < self.exact_out_prob;
             out.push(RetailOrderV2 {
                 token_in,
                 token_out,
-                size_numeraire: self.lognormal.sample(&mut self.rng),
+                size_numeraire,
+                exact_out,
             });
         }
         out
@@ -1,8 +1,11 @@
 //! Order router with optimal splitting across multiple AMMs.
 
-use crate::amm::CFMM;
+use crate::amm::{CurveKind, PricingCurve, CFMM};
 use crate::market::retail::RetailOrder;
 
+/// Bisection iterations for the generic (non closed-form) split.
+const SPLIT_ITERATIONS: usize = 100;
+
 /// Result of routing a trade to an AMM.
 #[derive(Debug, Clone)]
 pub struct RoutedTrade {
@@ -23,7 +26,13 @@ pub struct RoutedTrade {
 /// the trader and creates fair competition between AMMs based on their fees.
 ///
 /// For constant product AMMs (xy=k), the optimal split can be computed
-/// analytically rather than using numerical methods.
+/// analytically rather than using numerical methods. Other curve pairs,
+/// and pools charging fees on output, are split by bisecting on the common
+/// post-trade marginal price.
+///
+/// Pools whose strategy prices trades in `beforeSwap` are split at the fee
+/// the hook quotes for the whole order, then execute at the fee it returns
+/// for their leg.
 pub struct OrderRouter;
 
 impl OrderRouter {
@@ -33,11 +42,75 @@ impl OrderRouter {
     }
 
     /// Compute optimal Y split for buying X across two AMMs.
+    fn split_buy_two_amms(&self, amm1: &CFMM, amm2: &CFMM, total_y: f64) -> (f64, f64) {
+        let fee_on_input = amm1.fee_mode().fee_on_input() && amm2.fee_mode().fee_on_input();
+        if amm1.has_before_swap() || amm2.has_before_swap() {
+            return split_buy_marginal(
+                CurveView::ask(amm1, total_y),
+                CurveView::ask(amm2, total_y),
+                total_y,
+            );
+        }
+        match (amm1.curve().kind(), amm2.curve().kind()) {
+            (CurveKind::ConstantProduct, CurveKind::ConstantProduct) if fee_on_input => {
+                self.split_buy_constant_product(amm1, amm2, total_y)
+            }
+            (CurveKind::Weighted { weight: w1 }, CurveKind::Weighted { weight: w2 })
+                if w1 == w2 && fee_on_input =>
+            {
+                split_buy_weighted(
+                    CurveView::ask(amm1, total_y),
+                    CurveView::ask(amm2, total_y),
+                    total_y,
+                    w1,
+                )
+            }
+            _ => split_buy_marginal(
+                CurveView::ask(amm1, total_y),
+                CurveView::ask(amm2, total_y),
+                total_y,
+            ),
+        }
+    }
+
+    /// Compute optimal X split for selling X across two AMMs.
+    fn split_sell_two_amms(&self, amm1: &CFMM, amm2: &CFMM, total_x: f64) -> (f64, f64) {
+        let fee_on_input = amm1.fee_mode().fee_on_input() && amm2.fee_mode().fee_on_input();
+        if amm1.has_before_swap() || amm2.has_before_swap() {
+            return split_sell_marginal(
+                CurveView::bid(amm1, total_x),
+                CurveView::bid(amm2, total_x),
+                total_x,
+            );
+        }
+        match (amm1.curve().kind(), amm2.curve().kind()) {
+            (CurveKind::ConstantProduct, CurveKind::ConstantProduct) if fee_on_input => {
+                self.split_sell_constant_product(amm1, amm2, total_x)
+            }
+            (CurveKind::Weighted { weight: w1 }, CurveKind::Weighted { weight: w2 })
+                if w1 == w2 && fee_on_input =>
+            {
+                split_sell_weighted(
+                    CurveView::bid(amm1, total_x),
+                    CurveView::bid(amm2, total_x),
+                    total_x,
+                    w1,
+                )
+            }
+            _ => split_sell_marginal(
+                CurveView::bid(amm1, total_x),
+                CurveView::bid(amm2, total_x),
+                total_x,
+            ),
+        }
+    }
+
+    /// Closed-form Y split for buying X across two constant product AMMs.
     ///
     /// Uses Uniswap v2 fee-on-input model with γ = 1 - f:
     /// - A_i = sqrt(x_i * γ_i * y_i), r = A_1/A_2
     /// - Δy_1* = (r * (y_2 + γ_2 * Y) - y_1) / (γ_1 + r * γ_2)
-    fn split_buy_two_amms(&self, amm1: &CFMM, amm2: &CFMM, total_y: f64) -> (f64, f64) {
+    fn split_buy_constant_product(&self, amm1: &CFMM, amm2: &CFMM, total_y: f64) -> (f64, f64) {
         let (x1, y1) = amm1.reserves();
         let (x2, y2) = amm2.reserves();
         let f1 = amm1.fees().ask_fee.to_f64();
@@ -74,12 +147,12 @@ impl OrderRouter {
         (y1_amount, y2_amount)
     }
 
-    /// Compute optimal X split for selling X across two AMMs.
+    /// Closed-form X split for selling X across two constant product AMMs.
     ///
     /// Uses Uniswap v2 fee-on-input model with γ = 1 - f:
     /// - B_i = sqrt(y_i * γ_i * x_i), r = B_1/B_2
     /// - Δx_1* = (r * (x_2 + γ_2 * X) - x_1) / (γ_1 + r * γ_2)
-    fn split_sell_two_amms(&self, amm1: &CFMM, amm2: &CFMM, total_x: f64) -> (f64, f64) {
+    fn split_sell_constant_product(&self, amm1: &CFMM, amm2: &CFMM, total_x: f64) -> (f64, f64) {
         let (x1, y1) = amm1.reserves();
         let (x2, y2) = amm2.reserves();
         let f1 = amm1.fees().bid_fee.to_f64();
@@ -151,6 +224,12 @@ impl OrderRouter {
     ) -> Vec<RoutedTrade> {
         let mut trades = Vec::new();
 
+        if order.exact_out {
+            let total = exact_out_total(order, fair_price);
+            trades.extend(execute_exact_out_leg(order, amm, total, timestamp));
+            return trades;
+        }
+
         if order.side == "buy" {
             // Trader wants to buy X, spending Y
             if let Some(result) = amm.execute_buy_x_with_y(order.size, timestamp) {
@@ -191,6 +270,33 @@ impl OrderRouter {
         let (amm1, rest) = amms.split_first_mut().unwrap();
         let amm2 = &mut rest[0];
 
+        if order.exact_out {
+            let total = exact_out_total(order, fair_price);
+            let (out1, out2) = if order.side == "buy" {
+                split_by_rising_price(
+                    CurveView::ask(amm1, total * amm1.spot_price()),
+                    CurveView::ask(amm2, total * amm2.spot_price()),
+                    total,
+                    CurveView::x_out_to_reach,
+                )
+            } else {
+                split_by_falling_price(
+                    CurveView::bid(amm1, total / amm1.spot_price()),
+                    CurveView::bid(amm2, total / amm2.spot_price()),
+                    total,
+                    CurveView::y_out_to_reach,
+                )
+            };
+
+            if out1 > MIN_AMOUNT {
+                trades.extend(execute_exact_out_leg(order, amm1, out1, timestamp));
+            }
+            if out2 > MIN_AMOUNT {
+                trades.extend(execute_exact_out_leg(order, amm2, out2, timestamp));
+            }
+            return trades;
+        }
+
         if order.side == "buy" {
             // Trader wants to buy X, spending Y
             let (y1, y2) = self.split_buy_two_amms(amm1, amm2, order.size);
@@ -282,6 +388,328 @@ impl OrderRouter {
     }
 }
 
+/// Pool state relevant to splitting one side of an order.
+#[derive(Clone, Copy)]
+struct CurveView<'a> {
+    curve: &'a dyn PricingCurve,
+    reserve_x: f64,
+    reserve_y: f64,
+    gamma: f64,
+    /// Whether the fee is deducted from the input (otherwise from the output)
+    fee_on_input: bool,
+}
+
+impl<'a> CurveView<'a> {
+    /// View for trades where the AMM sells X (ask fee applies), for an
+    /// order paying about `amount_in` Y.
+    fn ask(amm: &'a CFMM, amount_in: f64) -> Self {
+        Self::with_fee(amm, amm.fee_for(false, amount_in).to_f64())
+    }
+
+    /// View for trades where the AMM buys X (bid fee applies), for an
+    /// order paying about `amount_in` X.
+    fn bid(amm: &'a CFMM, amount_in: f64) -> Self {
+        Self::with_fee(amm, amm.fee_for(true, amount_in).to_f64())
+    }
+
+    fn with_fee(amm: &'a CFMM, fee: f64) -> Self {
+        let (reserve_x, reserve_y) = amm.reserves();
+        Self {
+            curve: amm.curve(),
+            reserve_x,
+            reserve_y,
+            gamma: 1.0 - fee,
+            fee_on_input: amm.fee_mode().fee_on_input(),
+        }
+    }
+
+    /// Gross input for a curve input of `net`.
+    fn gross_input(&self, net: f64) -> f64 {
+        if self.fee_on_input {
+            net / self.gamma
+        } else {
+            net
+        }
+    }
+
+    /// Output the trader receives for a curve output of `gross`.
+    fn net_output(&self, gross: f64) -> f64 {
+        if self.fee_on_input {
+            gross
+        } else {
+            gross * self.gamma
+        }
+    }
+
+    fn spot_price(&self) -> f64 {
+        self.curve.spot_price(self.reserve_x, self.reserve_y)
+    }
+
+    /// Gross Y needed to buy X until the trader's marginal price is `price`.
+    ///
+    /// Buying X moves the spot price up to q, where the trader's marginal
+    /// price is q / γ whether the fee is charged on input or on output.
+    fn y_to_reach(&self, price: f64) -> f64 {
+        let target = self.gamma * price;
+        if target <= self.spot_price() {
+            return 0.0;
+        }
+        let new_x = self
+            .curve
+            .reserve_x_at_price(self.reserve_x, self.reserve_y, target);
+        let x_out = self.reserve_x - new_x;
+        if x_out <= 0.0 {
+            return 0.0;
+        }
+        self.gross_input(
+            self.curve
+                .y_in_for_x_out(self.reserve_x, self.reserve_y, x_out),
+        )
+    }
+
+    /// Gross X needed to sell X until the trader's marginal price is `price`.
+    ///
+    /// Selling X moves the spot price down to q, where the trader's marginal
+    /// price is γ * q.
+    fn x_to_reach(&self, price: f64) -> f64 {
+        let target = price / self.gamma;
+        if target >= self.spot_price() {
+            return 0.0;
+        }
+        let new_x = self
+            .curve
+            .reserve_x_at_price(self.reserve_x, self.reserve_y, target);
+        self.gross_input(new_x - self.reserve_x).max(0.0)
+    }
+
+    /// X received for buying X until the trader's marginal price is `price`.
+    fn x_out_to_reach(&self, price: f64) -> f64 {
+        let target = self.gamma * price;
+        if target <= self.spot_price() {
+            return 0.0;
+        }
+        let new_x = self
+            .curve
+            .reserve_x_at_price(self.reserve_x, self.reserve_y, target);
+        self.net_output(self.reserve_x - new_x).max(0.0)
+    }
+
+    /// Y received for selling X until the trader's marginal price is `price`.
+    fn y_out_to_reach(&self, price: f64) -> f64 {
+        let target = price / self.gamma;
+        if target >= self.spot_price() {
+            return 0.0;
+        }
+        let new_x = self
+            .curve
+            .reserve_x_at_price(self.reserve_x, self.reserve_y, target);
+        let x_in = new_x - self.reserve_x;
+        if x_in <= 0.0 {
+            return 0.0;
+        }
+        self.net_output(
+            self.curve
+                .y_out_for_x_in(self.reserve_x, self.reserve_y, x_in),
+        )
+    }
+}
+
+/// Size of an exact-output order in the token received: X for buys, Y for sells.
+fn exact_out_total(order: &RetailOrder, fair_price: f64) -> f64 {
+    if order.side == "buy" {
+        order.size / fair_price
+    } else {
+        order.size
+    }
+}
+
+/// Execute one leg of an exact-output order, receiving `amount_out`.
+fn execute_exact_out_leg(
+    order: &RetailOrder,
+    amm: &mut CFMM,
+    amount_out: f64,
+    timestamp: u64,
+) -> Option<RoutedTrade> {
+    if order.side == "buy" {
+        // Trader receives exactly amount_out X, paying Y
+        let result = amm.execute_sell_x(amount_out, timestamp)?;
+        Some(RoutedTrade {
+            amm_name: amm.name.clone(),
+            amount_y: result.trade_info.amount_y.to_f64(),
+            amount_x: amount_out,
+            amm_buys_x: false,
+        })
+    } else {
+        // Trader receives exactly amount_out Y, paying X
+        let result = amm.execute_buy_x_for_y(amount_out, timestamp)?;
+        Some(RoutedTrade {
+            amm_name: amm.name.clone(),
+            amount_y: amount_out,
+            amount_x: result.trade_info.amount_x.to_f64(),
+            amm_buys_x: true,
+        })
+    }
+}
+
+/// Closed-form Y split for buying X across two weighted AMMs with equal weight w.
+///
+/// Post-trade marginal prices are equal when the post-trade Y reserves satisfy
+/// y_1' / y_2' = R, with c_i = x_i * y_i^((1-w)/w) and R = (c_1 γ_1 / (c_2 γ_2))^w:
+/// - Δy_1* = (R * (y_2 + γ_2 * Y) - y_1) / (γ_1 + R * γ_2)
+///
+/// With w = 0.5 this is the constant product split.
+fn split_buy_weighted(pool1: CurveView, pool2: CurveView, total_y: f64, weight: f64) -> (f64, f64) {
+    let (x1, y1, gamma1) = (pool1.reserve_x, pool1.reserve_y, pool1.gamma);
+    let (x2, y2, gamma2) = (pool2.reserve_x, pool2.reserve_y, pool2.gamma);
+    if gamma2 <= 0.0 {
+        return (total_y, 0.0);
+    }
+
+    // ln(c_1 γ_1 / (c_2 γ_2)), computed in log space to avoid overflow
+    let e = (1.0 - weight) / weight;
+    let log_ratio = (x1 / x2).ln() + e * (y1 / y2).ln() + (gamma1 / gamma2).ln();
+    let r = (weight * log_ratio).exp();
+
+    let denominator = gamma1 + r * gamma2;
+    let y1_amount = if denominator == 0.0 {
+        total_y / 2.0
+    } else {
+        (r * (y2 + gamma2 * total_y) - y1) / denominator
+    };
+
+    let y1_amount = y1_amount.max(0.0).min(total_y);
+    (y1_amount, total_y - y1_amount)
+}
+
+/// Closed-form X split for selling X across two weighted AMMs with equal weight w.
+///
+/// Post-trade marginal prices are equal when the post-trade X reserves satisfy
+/// x_1' / x_2' = R, with d_i = y_i * x_i^(w/(1-w)) and R = (γ_1 d_1 / (γ_2 d_2))^(1-w):
+/// - Δx_1* = (R * (x_2 + γ_2 * X) - x_1) / (γ_1 + R * γ_2)
+fn split_sell_weighted(
+    pool1: CurveView,
+    pool2: CurveView,
+    total_x: f64,
+    weight: f64,
+) -> (f64, f64) {
+    let (x1, y1, gamma1) = (pool1.reserve_x, pool1.reserve_y, pool1.gamma);
+    let (x2, y2, gamma2) = (pool2.reserve_x, pool2.reserve_y, pool2.gamma);
+    if gamma2 <= 0.0 {
+        return (total_x, 0.0);
+    }
+
+    // ln(γ_1 d_1 / (γ_2 d_2)), computed in log space to avoid overflow
+    let e = weight / (1.0 - weight);
+    let log_ratio = (y1 / y2).ln() + e * (x1 / x2).ln() + (gamma1 / gamma2).ln();
+    let r = ((1.0 - weight) * log_ratio).exp();
+
+    let denominator = gamma1 + r * gamma2;
+    let x1_amount = if denominator == 0.0 {
+        total_x / 2.0
+    } else {
+        (r * (x2 + gamma2 * total_x) - x1) / denominator
+    };
+
+    let x1_amount = x1_amount.max(0.0).min(total_x);
+    (x1_amount, total_x - x1_amount)
+}
+
+/// Y split for buying X across two AMMs with arbitrary curves.
+fn split_buy_marginal(pool1: CurveView, pool2: CurveView, total_y: f64) -> (f64, f64) {
+    split_by_rising_price(pool1, pool2, total_y, CurveView::y_to_reach)
+}
+
+/// X split for selling X across two AMMs with arbitrary curves.
+fn split_sell_marginal(pool1: CurveView, pool2: CurveView, total_x: f64) -> (f64, f64) {
+    split_by_falling_price(pool1, pool2, total_x, CurveView::x_to_reach)
+}
+
+/// Split an order that pushes the trader's marginal price up (buying X).
+///
+/// Bisects on the common post-trade marginal price P so that `amount_at(P)`
+/// summed over both pools equals `total`. `amount_at` may measure the order
+/// in either the token paid or the token received.
+fn split_by_rising_price<'a, F>(
+    pool1: CurveView<'a>,
+    pool2: CurveView<'a>,
+    total: f64,
+    amount_at: F,
+) -> (f64, f64)
+where
+    F: Fn(&CurveView<'a>, f64) -> f64,
+{
+    if pool1.gamma <= 0.0 || pool2.gamma <= 0.0 {
+        return if pool1.gamma >= pool2.gamma {
+            (total, 0.0)
+        } else {
+            (0.0, total)
+        };
+    }
+
+    let total_at = |price: f64| amount_at(&pool1, price) + amount_at(&pool2, price);
+
+    let mut lo = (pool1.spot_price() / pool1.gamma).min(pool2.spot_price() / pool2.gamma);
+    let mut hi = lo * 2.0;
+    while total_at(hi) < total && hi.is_finite() {
+        lo = hi;
+        hi *= 2.0;
+    }
+    for _ in 0..SPLIT_ITERATIONS {
+        let mid = 0.5 * (lo + hi);
+        if total_at(mid) < total {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    // Evaluate below the bracket: a pool whose liquidity runs out (e.g. a
+    // concentrated position leaving its range) needs unbounded Y above it.
+    let amount1 = amount_at(&pool1, lo).max(0.0).min(total);
+    (amount1, total - amount1)
+}
+
+/// Split an order that pushes the trader's marginal price down (selling X).
+///
+/// Mirror of `split_by_rising_price`.
+fn split_by_falling_price<'a, F>(
+    pool1: CurveView<'a>,
+    pool2: CurveView<'a>,
+    total: f64,
+    amount_at: F,
+) -> (f64, f64)
+where
+    F: Fn(&CurveView<'a>, f64) -> f64,
+{
+    if pool1.gamma <= 0.0 || pool2.gamma <= 0.0 {
+        return if pool1.gamma >= pool2.gamma {
+            (total, 0.0)
+        } else {
+            (0.0, total)
+        };
+    }
+
+    let total_at = |price: f64| amount_at(&pool1, price) + amount_at(&pool2, price);
+
+    let mut hi = (pool1.spot_price() * pool1.gamma).max(pool2.spot_price() * pool2.gamma);
+    let mut lo = hi * 0.5;
+    while total_at(lo) < total && lo > f64::MIN_POSITIVE {
+        hi = lo;
+        lo *= 0.5;
+    }
+    for _ in 0..SPLIT_ITERATIONS {
+        let mid = 0.5 * (lo + hi);
+        if total_at(mid) < total {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+
+    let amount1 = amount_at(&pool1, lo).max(0.0).min(total);
+    (amount1, total - amount1)
+}
+
 impl Default for OrderRouter {
     fn default() -> Self {
         Self::new()
@@ -291,13 +719,14 @@ impl Default for OrderRouter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::amm::{ConcentratedLiquidity, ConstantProduct, WeightedProduct};
 
     #[test]
     fn test_split_formulas() {
         // Test the split formulas without EVM
-        let x1 = 1000.0;
+        let x1: f64 = 1000.0;
         let y1 = 1000.0;
-        let x2 = 1000.0;
+        let x2: f64 = 1000.0;
         let y2 = 1000.0;
         let f = 0.0025;
         let gamma = 1.0 - f;
@@ -315,4 +744,142 @@ mod tests {
         // Should be approximately equal split
         assert!((y1_amount - 50.0).abs() < 1.0);
     }
+
+    #[test]
+    fn test_marginal_split_matches_constant_product_closed_form() {
+        let curve = ConstantProduct;
+        let (x1, y1, gamma1): (f64, f64, f64) = (1000.0, 1100.0, 1.0 - 0.003);
+        let (x2, y2, gamma2): (f64, f64, f64) = (900.0, 1000.0, 1.0 - 0.001);
+        let pool1 = CurveView {
+            curve: &curve,
+            reserve_x: x1,
+            reserve_y: y1,
+            gamma: gamma1,
+            fee_on_input: true,
+        };
+        let pool2 = CurveView {
+            curve: &curve,
+            reserve_x: x2,
+            reserve_y: y2,
+            gamma: gamma2,
+            fee_on_input: true,
+        };
+
+        // Buy X with Y
+        let total_y = 100.0;
+        let r = (x1 * gamma1 * y1).sqrt() / (x2 * gamma2 * y2).sqrt();
+        let y1_closed = (r * (y2 + gamma2 * total_y) - y1) / (gamma1 + r * gamma2);
+        let (y1_numeric, y2_numeric) = split_buy_marginal(pool1, pool2, total_y);
+        assert!((y1_numeric - y1_closed).abs() < 1e-6);
+        assert!((y1_numeric + y2_numeric - total_y).abs() < 1e-9);
+
+        // Sell X for Y
+        let total_x = 50.0;
+        let r = (y1 * gamma1 * x1).sqrt() / (y2 * gamma2 * x2).sqrt();
+        let x1_closed = (r * (x2 + gamma2 * total_x) - x1) / (gamma1 + r * gamma2);
+        let (x1_numeric, x2_numeric) = split_sell_marginal(pool1, pool2, total_x);
+        assert!((x1_numeric - x1_closed).abs() < 1e-6);
+        assert!((x1_numeric + x2_numeric - total_x).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_weighted_split_matches_marginal_split() {
+        let curve = WeightedProduct::new(0.8).unwrap();
+        let pool1 = CurveView {
+            curve: &curve,
+            reserve_x: 100.0,
+            reserve_y: 2600.0,
+            gamma: 1.0 - 0.003,
+            fee_on_input: true,
+        };
+        let pool2 = CurveView {
+            curve: &curve,
+            reserve_x: 90.0,
+            reserve_y: 2500.0,
+            gamma: 1.0 - 0.001,
+            fee_on_input: true,
+        };
+
+        let (y1_closed, _) = split_buy_weighted(pool1, pool2, 300.0, 0.8);
+        let (y1_numeric, _) = split_buy_marginal(pool1, pool2, 300.0);
+        assert!((y1_closed - y1_numeric).abs() < 1e-6);
+
+        let (x1_closed, _) = split_sell_weighted(pool1, pool2, 5.0, 0.8);
+        let (x1_numeric, _) = split_sell_marginal(pool1, pool2, 5.0);
+        assert!((x1_closed - x1_numeric).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_marginal_split_caps_pool_leaving_range() {
+        let concentrated = ConcentratedLiquidity::new(&[(0.8, 1.05, 1.0)]).unwrap();
+        let constant_product = ConstantProduct;
+        let (cx, cy) = (10.0, 400.0);
+        let pool1 = CurveView {
+            curve: &concentrated,
+            reserve_x: cx,
+            reserve_y: cy,
+            gamma: 1.0,
+            fee_on_input: true,
+        };
+        let pool2 = CurveView {
+            curve: &constant_product,
+            reserve_x: 1000.0,
+            reserve_y: 1000.0,
+            gamma: 1.0,
+            fee_on_input: true,
+        };
+
+        // Y needed to push the concentrated pool to the top of its range
+        let capacity = concentrated.y_in_for_x_out(cx, cy, cx * (1.0 - 1e-12));
+        let total_y = 200.0;
+        let (y1, y2) = split_buy_marginal(pool1, pool2, total_y);
+        assert!(y1.is_finite() && y1 <= capacity + 1e-6);
+        assert!((y1 - capacity).abs() / capacity < 1e-3);
+        assert!((y1 + y2 - total_y).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_exact_out_split_equalizes_marginal_price() {
+        let curve = ConstantProduct;
+        let pool1 = CurveView {
+            curve: &curve,
+            reserve_x: 1000.0,
+            reserve_y: 1100.0,
+            gamma: 1.0 - 0.003,
+            fee_on_input: true,
+        };
+        let pool2 = CurveView {
+            curve: &curve,
+            reserve_x: 900.0,
+            reserve_y: 1000.0,
+            gamma: 1.0 - 0.001,
+            fee_on_input: false,
+        };
+
+        // Buying exactly 40 X: both pools end at the same marginal price
+        let (x1, x2) = split_by_rising_price(pool1, pool2, 40.0, CurveView::x_out_to_reach);
+        assert!((x1 + x2 - 40.0).abs() < 1e-9);
+        let gross2 = x2 / pool2.gamma;
+        let p1 = curve.spot_price(
+            pool1.reserve_x - x1,
+            pool1.reserve_y + curve.y_in_for_x_out(1000.0, 1100.0, x1),
+        ) / pool1.gamma;
+        let p2 = curve.spot_price(
+            pool2.reserve_x - gross2,
+            pool2.reserve_y + curve.y_in_for_x_out(900.0, 1000.0, gross2),
+        ) / pool2.gamma;
+        assert!((p1 - p2).abs() / p1 < 1e-6, "{} vs {}", p1, p2);
+
+        // Receiving exactly 30 Y costs no more X than sending it all to one pool
+        let (y1, y2) = split_by_falling_price(pool1, pool2, 30.0, CurveView::y_out_to_reach);
+        assert!((y1 + y2 - 30.0).abs() < 1e-9);
+        let x_in = |pool: &CurveView, y: f64| {
+            let gross = if pool.fee_on_input { y } else { y / pool.gamma };
+            let net = curve.x_in_for_y_out(pool.reserve_x, pool.reserve_y, gross);
+            pool.gross_input(net)
+        };
+        let split_cost = x_in(&pool1, y1) + x_in(&pool2, y2);
+        assert!(split_cost <= x_in(&pool1, 30.0) + 1e-9);
+        assert!(split_cost <= x_in(&pool2, 30.0) + 1e-9);
+    }
 }
@@ -2,9 +2,13 @@
 
 use std::collections::HashMap;
 
-use crate::amm::CFMM;
-use crate::evm::EVMStrategy;
-use crate::market::{Arbitrageur, GBMPriceProcess, OrderRouter, RetailTrader};
+use crate::amm::{FailurePolicy, FeeMode, CFMM};
+use crate::evm::strategy::EVMError;
+use crate::evm::{EVMStrategy, Revert, Violation};
+use crate::market::{
+    Arbitrageur, GBMPriceProcess, LiquidityFlow, OracleFeed, OrderRouter, RetailTrader,
+};
+use crate::simulation::metrics::{Flow, LvrTracker, MarkoutTracker};
 use crate::types::config::SimulationConfig;
 use crate::types::result::{LightweightSimResult, LightweightStepResult};
 
@@ -13,6 +17,35 @@ use crate::types::result::{LightweightSimResult, LightweightStepResult};
 pub enum SimulationError {
     EVMError(String),
     InvalidConfig(String),
+    /// A strategy call reverted; `strategy` is the positional name
+    /// ("submission" or "normalizer")
+    StrategyReverted {
+        strategy: String,
+        revert: Revert,
+    },
+    /// A strategy's runtime code contains forbidden instructions
+    ForbiddenBytecode {
+        strategy: String,
+        violations: Vec<Violation>,
+    },
+}
+
+impl SimulationError {
+    /// Wrap an EVM error raised by `strategy`, keeping reverts structured.
+    pub fn from_evm(strategy: &str, error: EVMError) -> Self {
+        match error {
+            EVMError::Reverted(revert) => SimulationError::StrategyReverted {
+                strategy: strategy.to_string(),
+                revert,
+            },
+            EVMError::InvalidConfig(message) => SimulationError::InvalidConfig(message),
+            EVMError::ForbiddenBytecode(violations) => SimulationError::ForbiddenBytecode {
+                strategy: strategy.to_string(),
+                violations,
+            },
+            other => SimulationError::EVMError(format!("{} strategy: {}", strategy, other)),
+        }
+    }
 }
 
 impl std::fmt::Display for SimulationError {
@@ -20,6 +53,22 @@ impl std::fmt::Display for SimulationError {
         match self {
             SimulationError::EVMError(s) => write!(f, "EVM error: {}", s),
             SimulationError::InvalidConfig(s) => write!(f, "Invalid config: {}", s),
+            SimulationError::StrategyReverted { strategy, revert } => {
+                write!(f, "EVM error: {} strategy {}", strategy, revert)
+            }
+            SimulationError::ForbiddenBytecode {
+                strategy,
+                violations,
+            } => {
+                write!(f, "Invalid bytecode: {} strategy uses ", strategy)?;
+                for (i, violation) in violations.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", violation)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -29,9 +78,20 @@ impl std::error::Error for SimulationError {}
 /// Main simulation engine for AMM competition.
 ///
 /// Runs a simulation with the following loop per step:
-/// 1. Generate new fair price via GBM
+/// 1. Generate new fair price via GBM (and pass the oracle price to the pools,
+///    and to the strategies when the oracle feed is enabled)
 /// 2. Arbitrageur extracts profit from each AMM
 /// 3. Retail orders arrive and are routed to best AMM
+/// 4. LPs deposit or withdraw liquidity
+/// 5. Strategies with an `onStep` hook get a per-step heartbeat
+/// 6. The step's strategy gas is charged to the shares outstanding
+///
+/// A strategy disqualified by the "disqualify" failure policy ends the
+/// simulation after the step it failed in, and is named in `disqualified`.
+///
+/// PnL is measured per LP share, so deposits and withdrawals do not count
+/// as profit or loss; `pnl` is the PnL of the initial LP position.
+/// Gas is charged like edge: to PnL and edge, per share and per step.
 pub struct SimulationEngine {
     config: SimulationConfig,
 }
@@ -57,6 +117,11 @@ impl SimulationEngine {
             self.config.gbm_sigma,
             self.config.gbm_dt,
             Some(seed),
+        )
+        .with_oracle(
+            self.config.oracle_lag as usize,
+            self.config.oracle_noise,
+            Some(seed + 2),
         );
 
         // Initialize retail trader with different seed
@@ -66,7 +131,8 @@ impl SimulationEngine {
             self.config.retail_size_sigma,
             self.config.retail_buy_prob,
             Some(seed + 1),
-        );
+        )
+        .with_exact_out_prob(self.config.retail_exact_out_prob);
 
         let arbitrageur = Arbitrageur::new();
         let router = OrderRouter::new();
@@ -76,20 +142,85 @@ impl SimulationEngine {
         let submission_name = "submission".to_string();
         let baseline_name = "normalizer".to_string();
 
-        let mut amm_submission =
-            CFMM::new(submission, self.config.initial_x, self.config.initial_y);
+        let fee_mode =
+            FeeMode::parse(&self.config.fee_mode).map_err(SimulationError::InvalidConfig)?;
+        let failure_policy =
+            FailurePolicy::parse(&self.config.failure_policy, self.config.failure_fee)
+                .map_err(SimulationError::InvalidConfig)?;
+        let curve_params = self.config.curve_params();
+        let build_curve = || {
+            curve_params
+                .build(self.config.initial_x, self.config.initial_y)
+                .map_err(SimulationError::InvalidConfig)
+        };
+
+        let mut amm_submission = CFMM::new_with_curve(
+            submission,
+            build_curve()?,
+            self.config.initial_x,
+            self.config.initial_y,
+            0,
+            1,
+            0,
+        );
         amm_submission.name = submission_name.clone();
+        amm_submission.set_fee_mode(fee_mode);
+        amm_submission.set_failure_policy(failure_policy);
+        amm_submission
+            .set_protocol_fee_fraction(self.config.protocol_fee)
+            .map_err(SimulationError::InvalidConfig)?;
+        if self.config.integer_math {
+            amm_submission
+                .enable_integer_math()
+                .map_err(SimulationError::InvalidConfig)?;
+        }
 
-        let mut amm_baseline = CFMM::new(baseline, self.config.initial_x, self.config.initial_y);
+        let mut amm_baseline = CFMM::new_with_curve(
+            baseline,
+            build_curve()?,
+            self.config.initial_x,
+            self.config.initial_y,
+            0,
+            1,
+            0,
+        );
         amm_baseline.name = baseline_name.clone();
+        amm_baseline.set_fee_mode(fee_mode);
+        amm_baseline.set_failure_policy(failure_policy);
+        amm_baseline
+            .set_protocol_fee_fraction(self.config.protocol_fee)
+            .map_err(SimulationError::InvalidConfig)?;
+        if self.config.integer_math {
+            amm_baseline
+                .enable_integer_math()
+                .map_err(SimulationError::InvalidConfig)?;
+        }
+
+        amm_submission.set_oracle_price(price_process.oracle_price());
+        amm_baseline.set_oracle_price(price_process.oracle_price());
+
+        // Oracle feed published to strategies, if enabled
+        let mut oracle_feed = self.config.oracle_feed.then(|| {
+            OracleFeed::new(
+                self.config.oracle_heartbeat,
+                self.config.oracle_deviation,
+                price_process.oracle_price(),
+            )
+        });
+        if let Some(feed) = &oracle_feed {
+            for amm in [&mut amm_submission, &mut amm_baseline] {
+                amm.publish_oracle(feed.price(), feed.updated_at())
+                    .map_err(|e| SimulationError::from_evm(&amm.name, e))?;
+            }
+        }
 
         // Initialize AMMs
         amm_submission
             .initialize()
-            .map_err(|e| SimulationError::EVMError(e.to_string()))?;
+            .map_err(|e| SimulationError::from_evm(&submission_name, e))?;
         amm_baseline
             .initialize()
-            .map_err(|e| SimulationError::EVMError(e.to_string()))?;
+            .map_err(|e| SimulationError::from_evm(&baseline_name, e))?;
 
         // Record initial state
         let initial_fair_price = price_process.current_price();
@@ -107,9 +238,13 @@ impl SimulationEngine {
         let mut edges: HashMap<String, f64> = HashMap::new();
         edges.insert(submission_name.clone(), 0.0);
         edges.insert(baseline_name.clone(), 0.0);
+        let mut edges_per_share: HashMap<String, f64> = HashMap::new();
+        edges_per_share.insert(submission_name.clone(), 0.0);
+        edges_per_share.insert(baseline_name.clone(), 0.0);
 
         // Run simulation steps
         let mut steps = Vec::with_capacity(self.config.n_steps as usize);
+        let mut disqualified: HashMap<String, String> = HashMap::new();
 
         // Store AMMs in a Vec for easier mutable access
         let mut amms = vec![amm_submission, amm_baseline];
@@ -121,6 +256,46 @@ impl SimulationEngine {
         // Track cumulative fees for averaging
         let mut cumulative_bid_fees: HashMap<String, f64> = HashMap::new();
         let mut cumulative_ask_fees: HashMap<String, f64> = HashMap::new();
+        // Protocol fees already deducted from edge, per AMM
+        let mut protocol_fees_seen = vec![(0.0_f64, 0.0_f64); amms.len()];
+        // Edge already converted to per-share edge, per AMM
+        let mut edges_seen = vec![0.0_f64; amms.len()];
+        // Gas already charged, and its cumulative cost per LP share
+        let mut gas_seen = vec![0_u64; amms.len()];
+        let mut gas_per_share: HashMap<String, f64> =
+            names.iter().map(|name| (name.clone(), 0.0)).collect();
+
+        // LP share baseline: PnL is measured against the initial share value
+        let lp_baseline: HashMap<String, LpBaseline> = amms
+            .iter()
+            .map(|amm| {
+                let baseline = LpBaseline {
+                    shares: amm.total_shares(),
+                    share_value: share_value(amm, initial_fair_price),
+                };
+                (amm.name.clone(), baseline)
+            })
+            .collect();
+        // LVR against a rebalancing portfolio, and per-trade markouts
+        let mut lvr_trackers: Vec<LvrTracker> = amms
+            .iter()
+            .map(|amm| LvrTracker::new(amm.reserves(), (initial_fair_price, 1.0)))
+            .collect();
+        let mut markouts: Vec<MarkoutTracker> = amms
+            .iter()
+            .map(|_| MarkoutTracker::new(self.config.n_steps as usize))
+            .collect();
+        let mut lp_flows: Vec<LiquidityFlow> = (0..amms.len() as u64)
+            .map(|i| {
+                LiquidityFlow::new(
+                    self.config.lp_schedule.clone(),
+                    self.config.lp_arrival_rate,
+                    self.config.lp_mean_size,
+                    self.config.lp_sensitivity,
+                    Some(seed + 3 + i),
+                )
+            })
+            .collect();
         for name in &names {
             arb_volume_y.insert(name.clone(), 0.0);
             retail_volume_y.insert(name.clone(), 0.0);
@@ -131,10 +306,31 @@ impl SimulationEngine {
         for t in 0..self.config.n_steps {
             // 1. Generate new fair price
             let fair_price = price_process.step();
+            for amm in amms.iter_mut() {
+                amm.set_oracle_price(price_process.oracle_price());
+            }
+            if let Some(feed) = oracle_feed.as_mut() {
+                if feed.observe(t as u64, price_process.oracle_price()) {
+                    for amm in amms.iter_mut() {
+                        amm.publish_oracle(feed.price(), feed.updated_at())
+                            .map_err(|e| SimulationError::from_evm(&amm.name, e))?;
+                    }
+                }
+            }
+
+            for tracker in markouts.iter_mut() {
+                tracker.start_step((fair_price, 1.0));
+            }
 
             // 2. Arbitrageur extracts profit from each AMM
-            for amm in amms.iter_mut() {
+            for (amm, tracker) in amms.iter_mut().zip(markouts.iter_mut()) {
                 if let Some(arb_result) = arbitrageur.execute_arb(amm, fair_price, t as u64) {
+                    tracker.record(
+                        Flow::Arbitrage,
+                        arb_result.side == "buy",
+                        arb_result.amount_x,
+                        arb_result.amount_y,
+                    );
                     *arb_volume_y.get_mut(&arb_result.amm_name).unwrap() += arb_result.amount_y;
                     let entry = edges.entry(arb_result.amm_name).or_insert(0.0);
                     // AMM edge is the negative of arbitrageur profit at true price
@@ -147,6 +343,14 @@ impl SimulationEngine {
             let routed_trades = router.route_orders(&orders, &mut amms, fair_price, t as u64);
             for trade in routed_trades {
                 *retail_volume_y.get_mut(&trade.amm_name).unwrap() += trade.amount_y;
+                if let Some(idx) = names.iter().position(|name| *name == trade.amm_name) {
+                    markouts[idx].record(
+                        Flow::Retail,
+                        trade.amm_buys_x,
+                        trade.amount_x,
+                        trade.amount_y,
+                    );
+                }
                 let trade_edge = if trade.amm_buys_x {
                     trade.amount_x * fair_price - trade.amount_y
                 } else {
@@ -156,15 +360,80 @@ impl SimulationEngine {
                 *entry += trade_edge;
             }
 
-            // 4. Capture step result and accumulate fees
+            // Protocol fees are not LP revenue: take this step's share out of edge
+            for (amm, seen) in amms.iter().zip(protocol_fees_seen.iter_mut()) {
+                let (px, py) = amm.protocol_fees();
+                let protocol_value = (px - seen.0) * fair_price + (py - seen.1);
+                if protocol_value != 0.0 {
+                    *edges.entry(amm.name.clone()).or_insert(0.0) -= protocol_value;
+                }
+                *seen = (px, py);
+            }
+
+            // Edge is earned by the shares outstanding when it is made
+            for (amm, seen) in amms.iter().zip(edges_seen.iter_mut()) {
+                let total = edges[&amm.name];
+                *edges_per_share.get_mut(&amm.name).unwrap() +=
+                    (total - *seen) / amm.total_shares();
+                *seen = total;
+            }
+
+            for (amm, tracker) in amms.iter().zip(lvr_trackers.iter_mut()) {
+                tracker.update(amm.reserves(), (fair_price, 1.0));
+            }
+
+            // 4. LPs deposit or withdraw at the current pool ratio
+            for ((amm, flow), tracker) in amms
+                .iter_mut()
+                .zip(lp_flows.iter_mut())
+                .zip(lvr_trackers.iter_mut())
+            {
+                let share_return =
+                    share_value(amm, fair_price) / lp_baseline[&amm.name].share_value - 1.0;
+                for fraction in flow.events(t, share_return) {
+                    if fraction > 0.0 {
+                        let (balance_x, balance_y) = amm.lp_balances();
+                        amm.add_liquidity(balance_x * fraction, balance_y * fraction);
+                    } else {
+                        amm.remove_liquidity(-fraction * amm.total_shares());
+                    }
+                }
+                tracker.rebase(amm.reserves());
+            }
+
+            // 5. Per-step heartbeat, whether or not the pool traded
+            for amm in amms.iter_mut() {
+                amm.on_step(t as u64);
+            }
+
+            // 6. Charge this step's gas (the first also covers afterInitialize)
+            for ((amm, seen), edge_seen) in amms
+                .iter()
+                .zip(gas_seen.iter_mut())
+                .zip(edges_seen.iter_mut())
+            {
+                let used = amm.gas().total();
+                let cost = (used - *seen) as f64 * self.config.gas_price;
+                *seen = used;
+                if cost != 0.0 {
+                    let cost_per_share = cost / amm.total_shares();
+                    *edges.get_mut(&amm.name).unwrap() -= cost;
+                    *edge_seen -= cost;
+                    *edges_per_share.get_mut(&amm.name).unwrap() -= cost_per_share;
+                    *gas_per_share.get_mut(&amm.name).unwrap() += cost_per_share;
+                }
+            }
+
+            // 7. Capture step result and accumulate fees
             let step = capture_step(
                 t,
                 fair_price,
                 &amms,
                 &names,
-                &initial_reserves,
-                initial_fair_price,
-            );
+                &lp_baseline,
+                &gas_per_share,
+                self.config.record_slots,
+            )?;
             // Accumulate fees for averaging
             for name in &names {
                 if let Some((bid_fee, ask_fee)) = step.fees.get(name) {
@@ -173,14 +442,25 @@ impl SimulationEngine {
                 }
             }
             steps.push(step);
+
+            // A disqualified strategy ends the simulation after this step;
+            // the result reports it rather than failing the whole batch
+            for amm in amms.iter_mut() {
+                if let Some(error) = amm.take_disqualification() {
+                    disqualified.insert(amm.name.clone(), error.to_string());
+                }
+            }
+            if !disqualified.is_empty() {
+                break;
+            }
         }
 
         // Calculate final PnL (reserves + accumulated fees)
         let final_fair_price = price_process.current_price();
         let mut pnl = HashMap::new();
 
-        // Calculate average fees
-        let n_steps = self.config.n_steps as f64;
+        // Calculate average fees over the steps run
+        let n_steps = steps.len() as f64;
         let mut average_fees: HashMap<String, (f64, f64)> = HashMap::new();
         for name in &names {
             let avg_bid = cumulative_bid_fees.get(name).unwrap() / n_steps;
@@ -188,16 +468,48 @@ impl SimulationEngine {
             average_fees.insert(name.clone(), (avg_bid, avg_ask));
         }
 
-        for (amm, name) in amms.iter().zip(names.iter()) {
-            let (init_x, init_y) = initial_reserves.get(name).unwrap();
-            let init_value = init_x * initial_fair_price + init_y;
-            let (final_x, final_y) = amm.reserves();
-            let (fees_x, fees_y) = amm.accumulated_fees();
-            let reserves_value = final_x * final_fair_price + final_y;
-            let fees_value = fees_x * final_fair_price + fees_y;
-            let final_value = reserves_value + fees_value;
-            pnl.insert(name.clone(), final_value - init_value);
+        let mut protocol_fees = HashMap::new();
+        let mut treasury = HashMap::new();
+        let mut pnl_per_share = HashMap::new();
+        let mut lp_shares = HashMap::new();
+        let mut lvr = HashMap::new();
+        let mut gas = HashMap::new();
+        let mut gas_cost = HashMap::new();
+        let mut failures = HashMap::new();
+        let mut arb_markouts = HashMap::new();
+        let mut retail_markouts = HashMap::new();
+        let horizons = &self.config.markout_horizons;
+        for (idx, (amm, name)) in amms.iter().zip(names.iter()).enumerate() {
+            lvr.insert(name.clone(), lvr_trackers[idx].lvr());
+            arb_markouts.insert(
+                name.clone(),
+                markouts[idx].markouts(Flow::Arbitrage, horizons),
+            );
+            retail_markouts.insert(name.clone(), markouts[idx].markouts(Flow::Retail, horizons));
+
+            let baseline = &lp_baseline[name];
+            let per_share =
+                share_value(amm, final_fair_price) - baseline.share_value - gas_per_share[name];
+            pnl_per_share.insert(name.clone(), per_share);
+            pnl.insert(name.clone(), per_share * baseline.shares);
+            lp_shares.insert(name.clone(), amm.total_shares());
+
+            let (protocol_x, protocol_y) = amm.protocol_fees();
+            protocol_fees.insert(name.clone(), (protocol_x, protocol_y));
+            treasury.insert(name.clone(), protocol_x * final_fair_price + protocol_y);
+
+            // Gas was charged against PnL and edge step by step
+            let cost = amm.gas().total() as f64 * self.config.gas_price;
+            gas.insert(name.clone(), amm.gas().stats_by_callback());
+            gas_cost.insert(name.clone(), cost);
+            failures.insert(name.clone(), amm.failures());
         }
+        let traces = amms
+            .iter_mut()
+            .zip(names.iter())
+            .map(|(amm, name)| (name.clone(), amm.take_traces()))
+            .filter(|(_, traces)| !traces.is_empty())
+            .collect();
 
         Ok(LightweightSimResult {
             seed,
@@ -210,21 +522,50 @@ impl SimulationEngine {
             arb_volume_y,
             retail_volume_y,
             average_fees,
+            protocol_fees,
+            treasury,
+            pnl_per_share,
+            edges_per_share,
+            lp_shares,
+            lvr,
+            arb_markouts,
+            retail_markouts,
+            gas,
+            gas_cost,
+            failures,
+            disqualified,
+            traces,
         })
     }
 }
 
+/// Initial LP share supply and value per share, in Y (numeraire for V2).
+pub(crate) struct LpBaseline {
+    pub(crate) shares: f64,
+    pub(crate) share_value: f64,
+}
+
+/// Value of one LP share (reserves plus LP fees) in Y at `fair_price`.
+fn share_value(amm: &CFMM, fair_price: f64) -> f64 {
+    let (balance_x, balance_y) = amm.lp_balances();
+    (balance_x * fair_price + balance_y) / amm.total_shares()
+}
+
 fn capture_step(
     timestamp: u32,
     fair_price: f64,
     amms: &[CFMM],
     names: &[String],
-    initial_reserves: &HashMap<String, (f64, f64)>,
-    initial_fair_price: f64,
-) -> LightweightStepResult {
+    lp_baseline: &HashMap<String, LpBaseline>,
+    gas_per_share: &HashMap<String, f64>,
+    record_slots: bool,
+) -> Result<LightweightStepResult, SimulationError> {
     let mut spot_prices = HashMap::new();
     let mut pnls = HashMap::new();
     let mut fees = HashMap::new();
+    let mut treasury = HashMap::new();
+    let mut lp_shares = HashMap::new();
+    let mut slots = HashMap::new();
 
     for (amm, name) in amms.iter().zip(names.iter()) {
         spot_prices.insert(name.clone(), amm.spot_price());
@@ -235,27 +576,179 @@ fn capture_step(
             (fee_quote.bid_fee.to_f64(), fee_quote.ask_fee.to_f64()),
         );
 
-        // Calculate running PnL (reserves + accumulated fees)
-        let (init_x, init_y) = initial_reserves.get(name).unwrap();
-        let init_value = init_x * initial_fair_price + init_y;
-        let (curr_x, curr_y) = amm.reserves();
-        let (fees_x, fees_y) = amm.accumulated_fees();
-        let reserves_value = curr_x * fair_price + curr_y;
-        let fees_value = fees_x * fair_price + fees_y;
-        let curr_value = reserves_value + fees_value;
-        pnls.insert(name.clone(), curr_value - init_value);
+        // Running PnL of the initial LP position (reserves + accumulated fees - gas)
+        let baseline = &lp_baseline[name];
+        let per_share = share_value(amm, fair_price) - baseline.share_value - gas_per_share[name];
+        pnls.insert(name.clone(), per_share * baseline.shares);
+        lp_shares.insert(name.clone(), amm.total_shares());
+
+        let (protocol_x, protocol_y) = amm.protocol_fees();
+        treasury.insert(name.clone(), protocol_x * fair_price + protocol_y);
+
+        if record_slots {
+            let values = amm
+                .strategy_slots()
+                .map_err(|e| SimulationError::from_evm(name, e))?;
+            slots.insert(name.clone(), values);
+        }
     }
 
-    LightweightStepResult {
+    Ok(LightweightStepResult {
         timestamp,
         fair_price,
         spot_prices,
         pnls,
         fees,
-    }
+        treasury,
+        lp_shares,
+        slots,
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    // Full tests require EVM bytecode - see integration tests
+    // Full tests require EVM bytecode - see integration tests; the
+    // contracts here are hand-assembled.
+    use super::*;
+    use crate::evm::test_support::{ON_STEP_FEE_INIT, REVERT_AFTER_SWAP_INIT, ZERO_FEE_INIT};
+    use crate::types::config::EvmConfig;
+
+    fn config(n_steps: u32) -> SimulationConfig {
+        SimulationConfig {
+            n_steps,
+            initial_price: 100.0,
+            initial_x: 100.0,
+            initial_y: 10_000.0,
+            gbm_mu: 0.0,
+            gbm_sigma: 0.01,
+            gbm_dt: 1.0,
+            retail_arrival_rate: 5.0,
+            retail_mean_size: 2.0,
+            retail_size_sigma: 0.7,
+            retail_buy_prob: 0.5,
+            seed: Some(7),
+            pool_weight: None,
+            stableswap_amp: None,
+            liquidity_ranges: Vec::new(),
+            pmm_k: None,
+            oracle_lag: 0,
+            oracle_noise: 0.0,
+            fee_mode: "input".to_string(),
+            protocol_fee: 0.0,
+            retail_exact_out_prob: 0.0,
+            integer_math: false,
+            lp_schedule: Vec::new(),
+            lp_arrival_rate: 0.0,
+            lp_mean_size: 0.05,
+            lp_sensitivity: 0.0,
+            oracle_feed: false,
+            oracle_heartbeat: 0,
+            oracle_deviation: 0.0,
+            markout_horizons: vec![1],
+            gas_price: 0.0,
+            record_slots: false,
+            failure_policy: "keep".to_string(),
+            failure_fee: 0.003,
+            evm: EvmConfig::default(),
+        }
+    }
+
+    /// Run `submission` against a zero-fee normalizer.
+    fn run(submission: &[u8], n_steps: u32) -> LightweightSimResult {
+        run_with(config(n_steps), submission)
+    }
+
+    fn run_with(config: SimulationConfig, submission: &[u8]) -> LightweightSimResult {
+        let strategy = |init: &[u8]| EVMStrategy::new(init.to_vec(), "test".into()).unwrap();
+        SimulationEngine::new(config)
+            .run(strategy(submission), strategy(&ZERO_FEE_INIT))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_on_step_fee_applies_from_the_next_step() {
+        // The fee posted by onStep at the end of step 0 is not charged in step 0
+        let (hooked, plain) = (run(&ON_STEP_FEE_INIT, 1), run(&ZERO_FEE_INIT, 1));
+        let (bid_fee, ask_fee) = hooked.steps[0].fees["submission"];
+        assert!((bid_fee - 0.01).abs() < 1e-12 && (ask_fee - 0.01).abs() < 1e-12);
+        assert_eq!(plain.steps[0].fees["submission"], (0.0, 0.0));
+        assert_eq!(hooked.pnl["submission"], plain.pnl["submission"]);
+
+        // ...but is from step 1 on
+        let (hooked, plain) = (run(&ON_STEP_FEE_INIT, 2), run(&ZERO_FEE_INIT, 2));
+        assert!((hooked.pnl["submission"] - plain.pnl["submission"]).abs() > 1e-6);
+        assert!(hooked.retail_volume_y["submission"] < plain.retail_volume_y["submission"]);
+    }
+
+    #[test]
+    fn test_gas_is_charged_everywhere_pnl_is_reported() {
+        let mut priced = config(3);
+        priced.gas_price = 1e-5;
+        let (charged, free) = (
+            run_with(priced, &ON_STEP_FEE_INIT),
+            run(&ON_STEP_FEE_INIT, 3),
+        );
+        let cost = charged.gas_cost["submission"];
+        assert!(cost > 0.0);
+        assert_eq!(free.gas_cost["submission"], 0.0);
+
+        // Gas does not change trading, so every gap is gas alone
+        let gap = |free: f64, charged: f64| free - charged;
+        let shares = charged.lp_shares["submission"];
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(
+            gap(free.pnl["submission"], charged.pnl["submission"]),
+            cost
+        ));
+        assert!(close(
+            gap(free.edges["submission"], charged.edges["submission"]),
+            cost
+        ));
+        assert!(close(
+            gap(
+                free.pnl_per_share["submission"],
+                charged.pnl_per_share["submission"]
+            ),
+            cost / shares
+        ));
+        assert!(close(
+            gap(
+                free.edges_per_share["submission"],
+                charged.edges_per_share["submission"]
+            ),
+            cost / shares
+        ));
+
+        // Step PnL carries the gas spent so far, ending at the final PnL
+        let step_gaps: Vec<f64> = free
+            .steps
+            .iter()
+            .zip(&charged.steps)
+            .map(|(free, charged)| gap(free.pnls["submission"], charged.pnls["submission"]))
+            .collect();
+        assert!(step_gaps[0] > 0.0);
+        assert!(step_gaps.windows(2).all(|pair| pair[1] > pair[0]));
+        assert!(close(step_gaps[2], cost));
+        assert!(close(
+            charged.steps[2].pnls["submission"],
+            charged.pnl["submission"]
+        ));
+    }
+
+    #[test]
+    fn test_disqualification_ends_the_simulation_with_a_result() {
+        let mut strict = config(10);
+        strict.failure_policy = "disqualify".to_string();
+        let result = run_with(strict, &REVERT_AFTER_SWAP_INIT);
+
+        // The first trade's afterSwap reverts, in step 0
+        assert_eq!(result.steps.len(), 1);
+        assert!(result.disqualified["submission"].starts_with("afterSwap reverted"));
+        assert!(!result.disqualified.contains_key("normalizer"));
+        assert!(result.pnl.contains_key("submission"));
+
+        let result = run(&REVERT_AFTER_SWAP_INIT, 10);
+        assert_eq!(result.steps.len(), 10);
+        assert!(result.disqualified.is_empty());
+    }
 }
@@ -2,12 +2,15 @@
 
 use std::collections::HashMap;
 
-use crate::amm::CFMM;
-use crate::evm::EVMStrategy;
-use crate::market::{Arbitrageur, MultiAssetPriceProcess, RetailTraderV2};
-use crate::simulation::engine::SimulationError;
+use crate::amm::{FailurePolicy, FeeMode, CFMM};
+use crate::evm::{DeployedStrategy, GasRecorder};
+use crate::market::{
+    Arbitrageur, LiquidityFlow, MultiAssetPriceProcess, OracleFeed, RetailTraderV2,
+};
+use crate::simulation::engine::{LpBaseline, SimulationError};
+use crate::simulation::metrics::{Flow, LvrTracker, MarkoutTracker};
 use crate::types::config::SimulationConfigV2;
-use crate::types::result::{LightweightSimResultV2, PoolStateV2};
+use crate::types::result::{FailureCounts, LightweightSimResultV2, PoolStateV2};
 
 /// Main simulation engine for multi-asset competition mode.
 pub struct SimulationEngineV2 {
@@ -19,10 +22,12 @@ impl SimulationEngineV2 {
         Self { config }
     }
 
+    /// Run a complete simulation. Every pool gets its own copy of each
+    /// deployed strategy.
     pub fn run(
         &mut self,
-        submission_bytecode: &[u8],
-        baseline_bytecode: &[u8],
+        submission: &DeployedStrategy,
+        baseline: &DeployedStrategy,
     ) -> Result<LightweightSimResultV2, SimulationError> {
         let seed = self.config.seed.unwrap_or(0);
         let n_assets = self.config.initial_prices.len();
@@ -37,7 +42,8 @@ impl SimulationEngineV2 {
                 "numeraire_token out of bounds".to_string(),
             ));
         }
-        if self.config.pools.is_empty() {
+        let pool_configs = self.config.resolved_pools();
+        if pool_configs.is_empty() {
             return Err(SimulationError::InvalidConfig(
                 "SimulationConfigV2 requires at least 1 pool".to_string(),
             ));
@@ -50,6 +56,11 @@ impl SimulationEngineV2 {
             self.config.gbm_sigma,
             self.config.gbm_dt,
             Some(seed),
+        )
+        .with_oracle(
+            self.config.oracle_lag as usize,
+            self.config.oracle_noise,
+            Some(seed + 2),
         );
         let mut retail_trader = RetailTraderV2::new(
             n_assets,
@@ -58,11 +69,20 @@ impl SimulationEngineV2 {
             self.config.retail_size_sigma,
             self.config.retail_buy_prob,
             Some(seed + 1),
-        );
+        )
+        .with_exact_out_prob(self.config.retail_exact_out_prob);
 
-        let mut amms: Vec<CFMM> = Vec::with_capacity(self.config.pools.len() * 2);
-        for (pool_idx, pool) in self.config.pools.iter().enumerate() {
-            let (token_a, token_b, initial_a, initial_b) = *pool;
+        let fee_mode =
+            FeeMode::parse(&self.config.fee_mode).map_err(SimulationError::InvalidConfig)?;
+        let failure_policy =
+            FailurePolicy::parse(&self.config.failure_policy, self.config.failure_fee)
+                .map_err(SimulationError::InvalidConfig)?;
+        let mut amms: Vec<CFMM> = Vec::with_capacity(pool_configs.len() * 2);
+        // Oracle feed per AMM, published to its strategy if enabled
+        let mut oracle_feeds: Vec<OracleFeed> = Vec::new();
+        for (pool_idx, pool) in pool_configs.iter().enumerate() {
+            let (token_a, token_b, initial_a, initial_b) =
+                (pool.token_a, pool.token_b, pool.initial_a, pool.initial_b);
             if token_a == token_b || token_a >= n_assets || token_b >= n_assets {
                 return Err(SimulationError::InvalidConfig(format!(
                     "Invalid pool at index {}: token indices must be distinct and in range",
@@ -76,14 +96,22 @@ impl SimulationEngineV2 {
                 )));
             }
 
-            let submission =
-                EVMStrategy::new(submission_bytecode.to_vec(), "submission".to_string())
-                    .map_err(|e| SimulationError::EVMError(e.to_string()))?;
-            let baseline = EVMStrategy::new(baseline_bytecode.to_vec(), "normalizer".to_string())
-                .map_err(|e| SimulationError::EVMError(e.to_string()))?;
+            // Oracle-anchored curves start from the oracle cross rate
+            let oracle_prices = price_process.oracle_prices();
+            let oracle_price = oracle_prices[token_b] / oracle_prices[token_a];
+            let curve_params = pool.curve_params();
+            let build_curve = || {
+                curve_params.build(initial_a, initial_b).map_err(|e| {
+                    SimulationError::InvalidConfig(format!(
+                        "Invalid pool at index {}: {}",
+                        pool_idx, e
+                    ))
+                })
+            };
 
-            let mut amm_submission = CFMM::new_with_pair(
-                submission,
+            let mut amm_submission = CFMM::new_with_curve(
+                submission.instantiate(),
+                build_curve()?,
                 initial_a,
                 initial_b,
                 token_a,
@@ -91,12 +119,31 @@ impl SimulationEngineV2 {
                 pool_idx * 2,
             );
             amm_submission.name = "submission".to_string();
+            amm_submission.set_oracle_price(oracle_price);
+            amm_submission.set_fee_mode(fee_mode);
+            amm_submission.set_failure_policy(failure_policy);
+            amm_submission
+                .set_protocol_fee_fraction(self.config.protocol_fee)
+                .map_err(SimulationError::InvalidConfig)?;
+            if self.config.integer_math {
+                amm_submission
+                    .enable_integer_math()
+                    .map_err(SimulationError::InvalidConfig)?;
+            }
+            if self.config.oracle_feed {
+                let feed = new_oracle_feed(&self.config, &price_process, token_a, token_b);
+                amm_submission
+                    .publish_oracle(feed.price(), feed.updated_at())
+                    .map_err(|e| SimulationError::from_evm(&amm_submission.name, e))?;
+                oracle_feeds.push(feed);
+            }
             amm_submission
                 .initialize_v2_or_fallback()
-                .map_err(|e| SimulationError::EVMError(e.to_string()))?;
+                .map_err(|e| SimulationError::from_evm(&amm_submission.name, e))?;
 
-            let mut amm_baseline = CFMM::new_with_pair(
-                baseline,
+            let mut amm_baseline = CFMM::new_with_curve(
+                baseline.instantiate(),
+                build_curve()?,
                 initial_a,
                 initial_b,
                 token_a,
@@ -104,39 +151,104 @@ impl SimulationEngineV2 {
                 pool_idx * 2 + 1,
             );
             amm_baseline.name = "normalizer".to_string();
+            amm_baseline.set_oracle_price(oracle_price);
+            amm_baseline.set_fee_mode(fee_mode);
+            amm_baseline.set_failure_policy(failure_policy);
+            amm_baseline
+                .set_protocol_fee_fraction(self.config.protocol_fee)
+                .map_err(SimulationError::InvalidConfig)?;
+            if self.config.integer_math {
+                amm_baseline
+                    .enable_integer_math()
+                    .map_err(SimulationError::InvalidConfig)?;
+            }
+            if self.config.oracle_feed {
+                let feed = new_oracle_feed(&self.config, &price_process, token_a, token_b);
+                amm_baseline
+                    .publish_oracle(feed.price(), feed.updated_at())
+                    .map_err(|e| SimulationError::from_evm(&amm_baseline.name, e))?;
+                oracle_feeds.push(feed);
+            }
             amm_baseline
                 .initialize_v2_or_fallback()
-                .map_err(|e| SimulationError::EVMError(e.to_string()))?;
+                .map_err(|e| SimulationError::from_evm(&amm_baseline.name, e))?;
 
             amms.push(amm_submission);
             amms.push(amm_baseline);
         }
 
-        let mut initial_value = HashMap::from([
-            ("submission".to_string(), 0.0_f64),
-            ("normalizer".to_string(), 0.0_f64),
-        ]);
-        for amm in &amms {
-            let (rx, ry) = amm.reserves();
-            let value = rx * price_process.current_prices()[amm.token_a]
-                + ry * price_process.current_prices()[amm.token_b];
-            *initial_value.get_mut(&amm.name).unwrap() += value;
-        }
-
-        let mut edges = HashMap::from([
-            ("submission".to_string(), 0.0_f64),
-            ("normalizer".to_string(), 0.0_f64),
-        ]);
+        // LP share baseline per AMM: PnL is measured against the initial share value
+        let lp_baseline: Vec<LpBaseline> = amms
+            .iter()
+            .map(|amm| LpBaseline {
+                shares: amm.total_shares(),
+                share_value: share_value(amm, price_process.current_prices()),
+            })
+            .collect();
+        // Edge per AMM, and the part of it already converted to per-share edge
+        let mut pool_edges = vec![0.0_f64; amms.len()];
+        let mut edges_seen = vec![0.0_f64; amms.len()];
+        let mut edges_per_share = vec![0.0_f64; amms.len()];
+        // Gas already charged, and its cumulative cost per LP share, per AMM
+        let mut gas_seen = vec![0_u64; amms.len()];
+        let mut gas_per_share = vec![0.0_f64; amms.len()];
+        let mut lp_flows: Vec<LiquidityFlow> = (0..amms.len() as u64)
+            .map(|i| {
+                LiquidityFlow::new(
+                    self.config.lp_schedule.clone(),
+                    self.config.lp_arrival_rate,
+                    self.config.lp_mean_size,
+                    self.config.lp_sensitivity,
+                    Some(seed + 3 + i),
+                )
+            })
+            .collect();
 
         let arbitrageur = Arbitrageur::new();
+        // LVR against a rebalancing portfolio, and per-trade markouts, per AMM
+        let mut lvr_trackers: Vec<LvrTracker> = amms
+            .iter()
+            .map(|amm| {
+                let prices = price_process.current_prices();
+                LvrTracker::new(amm.reserves(), (prices[amm.token_a], prices[amm.token_b]))
+            })
+            .collect();
+        let mut markouts: Vec<MarkoutTracker> = amms
+            .iter()
+            .map(|_| MarkoutTracker::new(self.config.n_steps as usize))
+            .collect();
+        // Protocol fees already deducted from edge, per AMM
+        let mut protocol_fees_seen = vec![(0.0_f64, 0.0_f64); amms.len()];
+        let mut disqualified: HashMap<String, String> = HashMap::new();
 
         for t in 0..self.config.n_steps {
-            let prices = price_process.step();
+            price_process.step();
+            let oracle_prices = price_process.oracle_prices();
+            for amm in amms.iter_mut() {
+                amm.set_oracle_price(oracle_prices[amm.token_b] / oracle_prices[amm.token_a]);
+            }
+            for (amm, feed) in amms.iter_mut().zip(oracle_feeds.iter_mut()) {
+                let oracle_price = oracle_prices[amm.token_b] / oracle_prices[amm.token_a];
+                if feed.observe(t as u64, oracle_price) {
+                    amm.publish_oracle(feed.price(), feed.updated_at())
+                        .map_err(|e| SimulationError::from_evm(&amm.name, e))?;
+                }
+            }
+            let prices = price_process.current_prices();
+            for (amm, tracker) in amms.iter().zip(markouts.iter_mut()) {
+                tracker.start_step((prices[amm.token_a], prices[amm.token_b]));
+            }
 
             // 2) Arbitrage each pool to current fair cross-rate.
-            for amm in amms.iter_mut() {
+            for (idx, (amm, tracker)) in amms.iter_mut().zip(markouts.iter_mut()).enumerate() {
                 let fair_price = prices[amm.token_b] / prices[amm.token_a];
                 if let Some(result) = arbitrageur.execute_arb(amm, fair_price, t as u64) {
+                    tracker.record(
+                        Flow::Arbitrage,
+                        result.side == "buy",
+                        result.amount_x,
+                        result.amount_y,
+                    );
                     // Generic edge in numeraire terms:
                     // AMM edge = value_in - value_out.
                     let edge = if result.side == "buy" {
@@ -148,37 +260,96 @@ impl SimulationEngineV2 {
                         result.amount_y * prices[amm.token_b]
                             - result.amount_x * prices[amm.token_a]
                     };
-                    *edges.get_mut(&amm.name).unwrap() += edge;
+                    pool_edges[idx] += edge;
                 }
             }
 
-            // 3) Generate retail orders and route to best direct pool.
+            // 3) Generate retail orders and route each to the best direct pool.
             let orders = retail_trader.generate_orders();
             for order in orders {
                 let token_in = order.token_in;
                 let token_out = order.token_out;
-                let amount_in = (order.size_numeraire / prices[token_in].max(1e-9)).max(1e-12);
-
-                let mut best_idx: Option<usize> = None;
-                let mut best_out = 0.0_f64;
-                for (idx, amm) in amms.iter().enumerate() {
-                    if let Some((out, _fee)) = amm.quote_exact_in(token_in, token_out, amount_in) {
-                        if out > best_out {
-                            best_out = out;
-                            best_idx = Some(idx);
-                        }
-                    }
+                // Exact-output orders fix the amount received, others the amount paid
+                let fixed_token = if order.exact_out { token_out } else { token_in };
+                let amount = (order.size_numeraire / prices[fixed_token].max(1e-9)).max(1e-12);
+
+                if let Some((idx, amount_in, amount_out, is_buy)) = route_retail(
+                    &mut amms,
+                    token_in,
+                    token_out,
+                    amount,
+                    order.exact_out,
+                    t as u64,
+                ) {
+                    record_retail(&mut markouts[idx], is_buy, amount_in, amount_out);
+                    pool_edges[idx] +=
+                        amount_in * prices[token_in] - amount_out * prices[token_out];
                 }
+            }
+
+            // Protocol fees are not LP revenue: take this step's share out of edge
+            for (idx, amm) in amms.iter().enumerate() {
+                let (pa, pb) = amm.protocol_fees();
+                let seen = &mut protocol_fees_seen[idx];
+                pool_edges[idx] -=
+                    (pa - seen.0) * prices[amm.token_a] + (pb - seen.1) * prices[amm.token_b];
+                *seen = (pa, pb);
+            }
 
-                if let Some(idx) = best_idx {
-                    if let Some((amount_out, _is_buy)) =
-                        amms[idx].execute_exact_in(token_in, token_out, amount_in, t as u64)
-                    {
-                        let edge = amount_in * prices[token_in] - amount_out * prices[token_out];
-                        let name = amms[idx].name.clone();
-                        *edges.get_mut(&name).unwrap() += edge;
+            // Edge is earned by the shares outstanding when it is made
+            for (idx, amm) in amms.iter().enumerate() {
+                edges_per_share[idx] += (pool_edges[idx] - edges_seen[idx]) / amm.total_shares();
+                edges_seen[idx] = pool_edges[idx];
+            }
+
+            for (amm, tracker) in amms.iter().zip(lvr_trackers.iter_mut()) {
+                tracker.update(amm.reserves(), (prices[amm.token_a], prices[amm.token_b]));
+            }
+
+            // 4) LPs deposit or withdraw at the current pool ratio.
+            for (idx, amm) in amms.iter_mut().enumerate() {
+                let share_return = share_value(amm, prices) / lp_baseline[idx].share_value - 1.0;
+                for fraction in lp_flows[idx].events(t, share_return) {
+                    if fraction > 0.0 {
+                        let (balance_a, balance_b) = amm.lp_balances();
+                        amm.add_liquidity(balance_a * fraction, balance_b * fraction);
+                    } else {
+                        amm.remove_liquidity(-fraction * amm.total_shares());
                     }
                 }
+                lvr_trackers[idx].rebase(amm.reserves());
+            }
+
+            // 5) Per-step heartbeat, whether or not the pool traded.
+            for amm in amms.iter_mut() {
+                amm.on_step(t as u64);
+            }
+
+            // 6) Charge this step's gas (the first also covers afterInitialize).
+            for (idx, amm) in amms.iter().enumerate() {
+                let used = amm.gas().total();
+                let cost = (used - gas_seen[idx]) as f64 * self.config.gas_price;
+                gas_seen[idx] = used;
+                if cost != 0.0 {
+                    let cost_per_share = cost / amm.total_shares();
+                    pool_edges[idx] -= cost;
+                    edges_seen[idx] -= cost;
+                    edges_per_share[idx] -= cost_per_share;
+                    gas_per_share[idx] += cost_per_share;
+                }
+            }
+
+            // 7) A disqualified strategy ends the simulation early, which is
+            // reported in the result rather than failing the whole batch.
+            for amm in amms.iter_mut() {
+                if let Some(error) = amm.take_disqualification() {
+                    disqualified
+                        .entry(amm.name.clone())
+                        .or_insert_with(|| error.to_string());
+                }
+            }
+            if !disqualified.is_empty() {
+                break;
             }
         }
 
@@ -186,29 +357,89 @@ impl SimulationEngineV2 {
             ("submission".to_string(), 0.0_f64),
             ("normalizer".to_string(), 0.0_f64),
         ]);
-        for amm in &amms {
-            let (rx, ry) = amm.reserves();
-            let (fx, fy) = amm.accumulated_fees();
-            let prices = price_process.current_prices();
-            let final_value = (rx + fx) * prices[amm.token_a] + (ry + fy) * prices[amm.token_b];
-            *pnl.get_mut(&amm.name).unwrap() += final_value;
+        let mut edges = pnl.clone();
+        let mut treasury = pnl.clone();
+        let prices = price_process.current_prices();
+        // PnL of the initial LP position in each pool (reserves + LP fees - gas)
+        let pnl_per_share: Vec<f64> = amms
+            .iter()
+            .enumerate()
+            .map(|(idx, amm)| {
+                share_value(amm, prices) - lp_baseline[idx].share_value - gas_per_share[idx]
+            })
+            .collect();
+        for (idx, amm) in amms.iter().enumerate() {
+            *pnl.get_mut(&amm.name).unwrap() += pnl_per_share[idx] * lp_baseline[idx].shares;
+            *edges.get_mut(&amm.name).unwrap() += pool_edges[idx];
+
+            let (pa, pb) = amm.protocol_fees();
+            *treasury.get_mut(&amm.name).unwrap() +=
+                pa * prices[amm.token_a] + pb * prices[amm.token_b];
         }
-        for (name, init) in &initial_value {
-            if let Some(total) = pnl.get_mut(name) {
-                *total -= init;
+
+        let mut lvr = HashMap::from([
+            ("submission".to_string(), 0.0_f64),
+            ("normalizer".to_string(), 0.0_f64),
+        ]);
+        let mut arb_markouts: HashMap<String, Vec<(u32, f64)>> = HashMap::new();
+        let mut retail_markouts: HashMap<String, Vec<(u32, f64)>> = HashMap::new();
+        let horizons = &self.config.markout_horizons;
+        for ((amm, lvr_tracker), tracker) in amms.iter().zip(&lvr_trackers).zip(&markouts) {
+            *lvr.get_mut(&amm.name).unwrap() += lvr_tracker.lvr();
+            for (flow, totals) in [
+                (Flow::Arbitrage, &mut arb_markouts),
+                (Flow::Retail, &mut retail_markouts),
+            ] {
+                let total = totals
+                    .entry(amm.name.clone())
+                    .or_insert_with(|| horizons.iter().map(|&h| (h, 0.0)).collect());
+                for (sum, (_, markout)) in total.iter_mut().zip(tracker.markouts(flow, horizons)) {
+                    sum.1 += markout;
+                }
             }
         }
 
+        // Gas over all pools, charged against PnL and edge step by step
+        let mut gas_used: HashMap<String, GasRecorder> = HashMap::new();
+        for amm in &amms {
+            gas_used
+                .entry(amm.name.clone())
+                .or_default()
+                .merge(amm.gas());
+        }
+        let mut gas = HashMap::new();
+        let mut gas_cost = HashMap::new();
+        for (name, recorder) in &gas_used {
+            let cost = recorder.total() as f64 * self.config.gas_price;
+            gas.insert(name.clone(), recorder.stats_by_callback());
+            gas_cost.insert(name.clone(), cost);
+        }
+
+        let mut failures: HashMap<String, FailureCounts> = HashMap::new();
+        for amm in &amms {
+            failures
+                .entry(amm.name.clone())
+                .or_default()
+                .merge(&amm.failures());
+        }
+
         let pools = amms
             .iter()
-            .map(|amm| {
+            .enumerate()
+            .map(|(idx, amm)| {
                 let (rx, ry) = amm.reserves();
+                let (pa, pb) = amm.protocol_fees();
                 PoolStateV2 {
                     pool_id: amm.pool_id,
                     token_a: amm.token_a,
                     token_b: amm.token_b,
                     reserve_a: rx,
                     reserve_b: ry,
+                    protocol_fee_a: pa,
+                    protocol_fee_b: pb,
+                    lp_shares: amm.total_shares(),
+                    pnl_per_share: pnl_per_share[idx],
+                    edge_per_share: edges_per_share[idx],
                 }
             })
             .collect();
@@ -218,8 +449,219 @@ impl SimulationEngineV2 {
             strategies: vec!["submission".to_string(), "normalizer".to_string()],
             pnl,
             edges,
-            final_prices: price_process.current_prices().to_vec(),
+            final_prices: prices.to_vec(),
             pools,
+            treasury,
+            lvr,
+            arb_markouts,
+            retail_markouts,
+            gas,
+            gas_cost,
+            failures,
+            disqualified,
         })
     }
 }
+
+/// Value of one LP share (reserves plus LP fees) in numeraire at `prices`.
+fn share_value(amm: &CFMM, prices: &[f64]) -> f64 {
+    let (balance_a, balance_b) = amm.lp_balances();
+    (balance_a * prices[amm.token_a] + balance_b * prices[amm.token_b]) / amm.total_shares()
+}
+
+/// Route a retail order whole to the direct pool with the best quote: the
+/// most output for exact-input orders, the least input for exact-output ones.
+///
+/// Unlike the V1 engine's `OrderRouter`, orders are never split across
+/// pools, so a large order pays the full price impact of a single pool.
+/// Returns (pool index, amount_in, amount_out, is_buy) for a filled order.
+fn route_retail(
+    amms: &mut [CFMM],
+    token_in: usize,
+    token_out: usize,
+    amount: f64,
+    exact_out: bool,
+    timestamp: u64,
+) -> Option<(usize, f64, f64, bool)> {
+    let mut best: Option<(usize, f64)> = None;
+    for (idx, amm) in amms.iter().enumerate() {
+        let quote = if exact_out {
+            amm.quote_exact_out(token_in, token_out, amount)
+                .map(|(amount_in, _fee)| -amount_in)
+        } else {
+            amm.quote_exact_in(token_in, token_out, amount)
+                .map(|(amount_out, _fee)| amount_out)
+                .filter(|&amount_out| amount_out > 0.0)
+        };
+        if let Some(score) = quote {
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((idx, score));
+            }
+        }
+    }
+
+    let (idx, _) = best?;
+    if exact_out {
+        let (amount_in, is_buy) =
+            amms[idx].execute_exact_out(token_in, token_out, amount, timestamp)?;
+        Some((idx, amount_in, amount, is_buy))
+    } else {
+        let (amount_out, is_buy) =
+            amms[idx].execute_exact_in(token_in, token_out, amount, timestamp)?;
+        Some((idx, amount, amount_out, is_buy))
+    }
+}
+
+/// Record a routed retail trade; `is_buy` is true when the pool buys token_a.
+fn record_retail(tracker: &mut MarkoutTracker, is_buy: bool, amount_in: f64, amount_out: f64) {
+    let (amount_a, amount_b) = if is_buy {
+        (amount_in, amount_out)
+    } else {
+        (amount_out, amount_in)
+    };
+    tracker.record(Flow::Retail, is_buy, amount_a, amount_b);
+}
+
+/// Oracle feed for a pool, starting from the current oracle cross rate
+/// (token_b per token_a).
+fn new_oracle_feed(
+    config: &SimulationConfigV2,
+    price_process: &MultiAssetPriceProcess,
+    token_a: usize,
+    token_b: usize,
+) -> OracleFeed {
+    let oracle_prices = price_process.oracle_prices();
+    OracleFeed::new(
+        config.oracle_heartbeat,
+        config.oracle_deviation,
+        oracle_prices[token_b] / oracle_prices[token_a],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::test_support::{COUNTER_INIT, FIXED_FEE_INIT, ON_STEP_FEE_INIT, ZERO_FEE_INIT};
+    use crate::evm::EVMStrategy;
+    use crate::types::config::EvmConfig;
+
+    fn pool(pool_id: usize, reserve_a: f64, reserve_b: f64) -> CFMM {
+        let strategy = EVMStrategy::new(COUNTER_INIT.to_vec(), "counter".into()).unwrap();
+        let mut amm = CFMM::new_with_pair(strategy, reserve_a, reserve_b, 0, 1, pool_id);
+        amm.initialize().unwrap();
+        amm
+    }
+
+    #[test]
+    fn test_retail_orders_are_not_split() {
+        // Splitting would fill more cheaply, but each order goes whole to
+        // the deeper pool and the shallower one is left untouched
+        for exact_out in [false, true] {
+            let mut amms = vec![pool(0, 1000.0, 1000.0), pool(1, 2000.0, 2000.0)];
+            let (idx, amount_in, amount_out, is_buy) =
+                route_retail(&mut amms, 0, 1, 100.0, exact_out, 1).unwrap();
+
+            assert_eq!(idx, 1);
+            assert!(is_buy);
+            assert_eq!(amms[0].reserves(), (1000.0, 1000.0));
+            let (rx, ry) = amms[1].reserves();
+            assert!((rx - 2000.0 - amount_in).abs() < 1e-9);
+            assert!((2000.0 - ry - amount_out).abs() < 1e-9);
+            if exact_out {
+                assert_eq!(amount_out, 100.0);
+            } else {
+                assert_eq!(amount_in, 100.0);
+            }
+        }
+    }
+
+    fn config(n_steps: u32) -> SimulationConfigV2 {
+        SimulationConfigV2 {
+            n_steps,
+            initial_prices: vec![1.0, 100.0],
+            gbm_mu: 0.0,
+            gbm_sigma: 0.01,
+            gbm_dt: 1.0,
+            retail_arrival_rate: 5.0,
+            retail_mean_size: 200.0,
+            retail_size_sigma: 0.7,
+            retail_buy_prob: 0.5,
+            numeraire_token: 0,
+            pools: vec![(0, 1, 100.0, 10_000.0)],
+            seed: Some(7),
+            pool_configs: Vec::new(),
+            fee_mode: "input".to_string(),
+            protocol_fee: 0.0,
+            retail_exact_out_prob: 0.0,
+            integer_math: false,
+            lp_schedule: Vec::new(),
+            lp_arrival_rate: 0.0,
+            lp_mean_size: 0.05,
+            lp_sensitivity: 0.0,
+            oracle_feed: false,
+            oracle_lag: 0,
+            oracle_noise: 0.0,
+            oracle_heartbeat: 0,
+            oracle_deviation: 0.0,
+            markout_horizons: vec![1],
+            gas_price: 0.0,
+            failure_policy: "keep".to_string(),
+            failure_fee: 0.003,
+            evm: EvmConfig::default(),
+        }
+    }
+
+    /// Run `submission` against a zero-fee normalizer.
+    fn run(submission: &[u8], n_steps: u32) -> LightweightSimResultV2 {
+        run_with(config(n_steps), submission)
+    }
+
+    fn run_with(config: SimulationConfigV2, submission: &[u8]) -> LightweightSimResultV2 {
+        let deploy = |init: &[u8]| DeployedStrategy::new(init.to_vec(), "test".into()).unwrap();
+        SimulationEngineV2::new(config)
+            .run(&deploy(submission), &deploy(&ZERO_FEE_INIT))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lp_flow_is_reported_per_share() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6 * b.abs().max(1.0);
+        let plain = run(&FIXED_FEE_INIT, 2);
+        let initial_shares = plain.pools[0].lp_shares;
+        assert!(close(initial_shares, 1000.0));
+
+        // Depositing after the last trades doubles the supply, but is not PnL
+        let mut late = config(2);
+        late.lp_schedule = vec![(1, 1.0)];
+        let late = run_with(late, &FIXED_FEE_INIT);
+        let (pool, plain_pool) = (&late.pools[0], &plain.pools[0]);
+        assert!(close(pool.lp_shares, 2.0 * initial_shares));
+        assert!(close(pool.pnl_per_share, plain_pool.pnl_per_share));
+        assert!(close(pool.edge_per_share, plain_pool.edge_per_share));
+        assert!(close(late.pnl["submission"], plain.pnl["submission"]));
+        assert!(close(late.edges["submission"], plain.edges["submission"]));
+
+        // An early deposit changes trading; PnL stays that of the initial shares
+        let mut early = config(5);
+        early.lp_schedule = vec![(0, 1.0), (2, -0.25)];
+        let early = run_with(early, &FIXED_FEE_INIT);
+        let pool = &early.pools[0];
+        assert!(close(pool.lp_shares, 1.5 * initial_shares));
+        assert!(close(
+            early.pnl["submission"],
+            pool.pnl_per_share * initial_shares
+        ));
+        assert!(pool.edge_per_share != 0.0);
+    }
+
+    #[test]
+    fn test_on_step_fee_applies_from_the_next_step() {
+        // The fee posted by onStep at the end of step 0 is not charged in step 0
+        let (hooked, plain) = (run(&ON_STEP_FEE_INIT, 1), run(&ZERO_FEE_INIT, 1));
+        assert_eq!(hooked.pnl["submission"], plain.pnl["submission"]);
+
+        // ...but is from step 1 on
+        let (hooked, plain) = (run(&ON_STEP_FEE_INIT, 2), run(&ZERO_FEE_INIT, 2));
+        assert!((hooked.pnl["submission"] - plain.pnl["submission"]).abs() > 1e-6);
+    }
+}
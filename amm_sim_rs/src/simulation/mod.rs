@@ -2,10 +2,12 @@
 
 pub mod engine;
 pub mod engine_v2;
+pub mod metrics;
 pub mod runner;
 
 pub use engine::SimulationEngine;
 pub use engine_v2::SimulationEngineV2;
+pub use metrics::{Flow, LvrTracker, MarkoutTracker};
 pub use runner::{
     run_simulations_parallel, run_simulations_parallel_v2, SimulationBatchConfig,
     SimulationBatchConfigV2,
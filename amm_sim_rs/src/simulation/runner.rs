@@ -2,10 +2,10 @@
 
 use rayon::prelude::*;
 
-use crate::evm::EVMStrategy;
+use crate::evm::DeployedStrategy;
 use crate::simulation::engine::{SimulationEngine, SimulationError};
 use crate::simulation::engine_v2::SimulationEngineV2;
-use crate::types::config::{SimulationConfig, SimulationConfigV2};
+use crate::types::config::{EvmConfig, SimulationConfig, SimulationConfigV2};
 use crate::types::result::{
     BatchSimulationResult, BatchSimulationResultV2, LightweightSimResult, LightweightSimResultV2,
 };
@@ -51,9 +51,13 @@ pub fn run_simulations_parallel(
             SimulationError::InvalidConfig(format!("Failed to create thread pool: {}", e))
         })?;
 
-    // Clone bytecodes for each worker (they need their own EVM instances)
-    let submission_bytecode = batch_config.submission_bytecode;
-    let baseline_bytecode = batch_config.baseline_bytecode;
+    // Deploy once; each simulation gets its own copy of the post-deploy state
+    let evm = batch_evm_config(batch_config.configs.iter().map(|config| &config.evm))?;
+    let (submission, baseline) = deploy_pair(
+        batch_config.submission_bytecode,
+        batch_config.baseline_bytecode,
+        &evm,
+    )?;
 
     // Run simulations in parallel
     let results: Result<Vec<LightweightSimResult>, SimulationError> = pool.install(|| {
@@ -61,16 +65,8 @@ pub fn run_simulations_parallel(
             .configs
             .into_par_iter()
             .map(|config| {
-                // Create fresh EVM strategies for this worker
-                let submission =
-                    EVMStrategy::new(submission_bytecode.clone(), "Submission".to_string())
-                        .map_err(|e| SimulationError::EVMError(e.to_string()))?;
-
-                let baseline = EVMStrategy::new(baseline_bytecode.clone(), "Baseline".to_string())
-                    .map_err(|e| SimulationError::EVMError(e.to_string()))?;
-
                 let mut engine = SimulationEngine::new(config);
-                engine.run(submission, baseline)
+                engine.run(submission.instantiate(), baseline.instantiate())
             })
             .collect()
     });
@@ -105,8 +101,12 @@ pub fn run_simulations_parallel_v2(
             SimulationError::InvalidConfig(format!("Failed to create thread pool: {}", e))
         })?;
 
-    let submission_bytecode = batch_config.submission_bytecode;
-    let baseline_bytecode = batch_config.baseline_bytecode;
+    let evm = batch_evm_config(batch_config.configs.iter().map(|config| &config.evm))?;
+    let (submission, baseline) = deploy_pair(
+        batch_config.submission_bytecode,
+        batch_config.baseline_bytecode,
+        &evm,
+    )?;
 
     let results: Result<Vec<LightweightSimResultV2>, SimulationError> = pool.install(|| {
         batch_config
@@ -114,7 +114,7 @@ pub fn run_simulations_parallel_v2(
             .into_par_iter()
             .map(|config| {
                 let mut engine = SimulationEngineV2::new(config);
-                engine.run(&submission_bytecode, &baseline_bytecode)
+                engine.run(&submission, &baseline)
             })
             .collect()
     });
@@ -138,19 +138,63 @@ pub fn run_simulation(
     baseline_bytecode: Vec<u8>,
     config: SimulationConfig,
 ) -> Result<LightweightSimResult, SimulationError> {
-    let submission = EVMStrategy::new(submission_bytecode, "Submission".to_string())
-        .map_err(|e| SimulationError::EVMError(e.to_string()))?;
-
-    let baseline = EVMStrategy::new(baseline_bytecode, "Baseline".to_string())
-        .map_err(|e| SimulationError::EVMError(e.to_string()))?;
+    let (submission, baseline) = deploy_pair(submission_bytecode, baseline_bytecode, &config.evm)?;
 
     let mut engine = SimulationEngine::new(config);
-    engine.run(submission, baseline)
+    engine.run(submission.instantiate(), baseline.instantiate())
+}
+
+/// The EVM settings shared by every simulation in a batch.
+///
+/// Strategies are deployed once per batch, so the settings must agree.
+fn batch_evm_config<'a>(
+    mut configs: impl Iterator<Item = &'a EvmConfig>,
+) -> Result<EvmConfig, SimulationError> {
+    let Some(first) = configs.next() else {
+        return Ok(EvmConfig::default());
+    };
+    if configs.any(|config| config != first) {
+        return Err(SimulationError::InvalidConfig(
+            "all simulations in a batch must use the same EVM config".into(),
+        ));
+    }
+    Ok(first.clone())
+}
+
+/// Deploy the submission and baseline strategies.
+fn deploy_pair(
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    evm: &EvmConfig,
+) -> Result<(DeployedStrategy, DeployedStrategy), SimulationError> {
+    let submission =
+        DeployedStrategy::with_config(submission_bytecode, "Submission".to_string(), evm)
+            .map_err(|e| SimulationError::from_evm("submission", e))?;
+
+    let baseline = DeployedStrategy::with_config(baseline_bytecode, "Baseline".to_string(), evm)
+        .map_err(|e| SimulationError::from_evm("normalizer", e))?;
+
+    Ok((submission, baseline))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // Full tests require EVM bytecode - see integration tests
+    #[test]
+    fn test_batch_evm_config_must_agree() {
+        let tight = EvmConfig {
+            gas_limit_swap: 100_000,
+            ..EvmConfig::default()
+        };
+        assert_eq!(
+            batch_evm_config(std::iter::empty()).unwrap(),
+            EvmConfig::default()
+        );
+        assert_eq!(
+            batch_evm_config([&tight, &tight].into_iter()).unwrap(),
+            tight
+        );
+        assert!(batch_evm_config([&tight, &EvmConfig::default()].into_iter()).is_err());
+    }
 }
@@ -1,6 +1,9 @@
 //! Simulation configuration.
 
 use pyo3::prelude::*;
+use revm::primitives::SpecId;
+
+use crate::amm::CurveParams;
 
 /// Configuration for a simulation run.
 #[pyclass]
@@ -53,6 +56,103 @@ pub struct SimulationConfig {
     /// Random seed for reproducibility (None = random)
     #[pyo3(get, set)]
     pub seed: Option<u64>,
+
+    /// Weight of X for weighted product pools (None = constant product)
+    #[pyo3(get, set)]
+    pub pool_weight: Option<f64>,
+
+    /// Amplification coefficient for StableSwap pools (None = constant product)
+    #[pyo3(get, set)]
+    pub stableswap_amp: Option<f64>,
+
+    /// Concentrated liquidity ranges as (price_lower, price_upper, liquidity_weight)
+    /// (empty = full-range curve)
+    #[pyo3(get, set)]
+    pub liquidity_ranges: Vec<(f64, f64, f64)>,
+
+    /// Slippage parameter k for oracle-anchored PMM pools (None = constant product)
+    #[pyo3(get, set)]
+    pub pmm_k: Option<f64>,
+
+    /// Oracle delay in steps (oracle = fair price `oracle_lag` steps ago)
+    #[pyo3(get, set)]
+    pub oracle_lag: u32,
+
+    /// Lognormal oracle noise (log-space sigma, 0 = exact)
+    #[pyo3(get, set)]
+    pub oracle_noise: f64,
+
+    /// Fee accounting: "input" (separate bucket), "output" or "compounding" (V2 style)
+    #[pyo3(get, set)]
+    pub fee_mode: String,
+
+    /// Share of every swap fee paid to the protocol treasury, in [0, 1]
+    #[pyo3(get, set)]
+    pub protocol_fee: f64,
+
+    /// Probability that a retail order fixes the amount received (exact-output)
+    #[pyo3(get, set)]
+    pub retail_exact_out_prob: f64,
+
+    /// Run pools on WAD integers with on-chain rounding (constant product only)
+    #[pyo3(get, set)]
+    pub integer_math: bool,
+
+    /// Scheduled LP events: (step, fraction of share supply), negative to withdraw
+    #[pyo3(get, set)]
+    pub lp_schedule: Vec<(u32, f64)>,
+
+    /// Expected stochastic LP events per step (0 = scheduled events only)
+    #[pyo3(get, set)]
+    pub lp_arrival_rate: f64,
+
+    /// Mean stochastic LP event size, as a fraction of share supply
+    #[pyo3(get, set)]
+    pub lp_mean_size: f64,
+
+    /// How strongly LPs deposit after gains and withdraw after losses (0 = unbiased)
+    #[pyo3(get, set)]
+    pub lp_sensitivity: f64,
+
+    /// Publish the oracle price to strategies (read with `oraclePrice()`)
+    #[pyo3(get, set)]
+    pub oracle_feed: bool,
+
+    /// Steps after which the feed publishes a round regardless of movement (0 = every step)
+    #[pyo3(get, set)]
+    pub oracle_heartbeat: u32,
+
+    /// Relative oracle move that publishes an early round (0 = every step)
+    #[pyo3(get, set)]
+    pub oracle_deviation: f64,
+
+    /// Horizons (in steps) at which trades are marked out against later fair prices
+    #[pyo3(get, set)]
+    pub markout_horizons: Vec<u32>,
+
+    /// Cost of one unit of strategy execution gas in the numeraire (Y for
+    /// single-pair runs), charged against PnL and edge as it is spent
+    /// (0 = gas is free)
+    #[pyo3(get, set)]
+    pub gas_price: f64,
+
+    /// Record the strategies' 32 storage slots in every step result
+    #[pyo3(get, set)]
+    pub record_slots: bool,
+
+    /// Response to a failed strategy callback: "keep" the last fees, post
+    /// `failure_fee` ("default"), post the maximum fee ("max_fee") or end the
+    /// simulation early, reporting the strategy as disqualified ("disqualify")
+    #[pyo3(get, set)]
+    pub failure_policy: String,
+
+    /// Fee posted after a failure under the "default" policy
+    #[pyo3(get, set)]
+    pub failure_fee: f64,
+
+    /// EVM execution settings (gas limits, hardfork, code-size and memory limits)
+    #[pyo3(get, set)]
+    pub evm: EvmConfig,
 }
 
 #[pymethods]
@@ -70,8 +170,32 @@ impl SimulationConfig {
         retail_mean_size,
         retail_size_sigma,
         retail_buy_prob,
-        seed
+        seed,
+        pool_weight = None,
+        stableswap_amp = None,
+        liquidity_ranges = Vec::new(),
+        pmm_k = None,
+        oracle_lag = 0,
+        oracle_noise = 0.0,
+        fee_mode = "input".to_string(),
+        protocol_fee = 0.0,
+        retail_exact_out_prob = 0.0,
+        integer_math = false,
+        lp_schedule = Vec::new(),
+        lp_arrival_rate = 0.0,
+        lp_mean_size = 0.05,
+        lp_sensitivity = 0.0,
+        oracle_feed = false,
+        oracle_heartbeat = 0,
+        oracle_deviation = 0.0,
+        markout_horizons = vec![1, 10, 100],
+        gas_price = 0.0,
+        record_slots = false,
+        failure_policy = "keep".to_string(),
+        failure_fee = 0.003,
+        evm = EvmConfig::default()
     ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         n_steps: u32,
         initial_price: f64,
@@ -85,6 +209,29 @@ impl SimulationConfig {
         retail_size_sigma: f64,
         retail_buy_prob: f64,
         seed: Option<u64>,
+        pool_weight: Option<f64>,
+        stableswap_amp: Option<f64>,
+        liquidity_ranges: Vec<(f64, f64, f64)>,
+        pmm_k: Option<f64>,
+        oracle_lag: u32,
+        oracle_noise: f64,
+        fee_mode: String,
+        protocol_fee: f64,
+        retail_exact_out_prob: f64,
+        integer_math: bool,
+        lp_schedule: Vec<(u32, f64)>,
+        lp_arrival_rate: f64,
+        lp_mean_size: f64,
+        lp_sensitivity: f64,
+        oracle_feed: bool,
+        oracle_heartbeat: u32,
+        oracle_deviation: f64,
+        markout_horizons: Vec<u32>,
+        gas_price: f64,
+        record_slots: bool,
+        failure_policy: String,
+        failure_fee: f64,
+        evm: EvmConfig,
     ) -> Self {
         Self {
             n_steps,
@@ -99,6 +246,29 @@ impl SimulationConfig {
             retail_size_sigma,
             retail_buy_prob,
             seed,
+            pool_weight,
+            stableswap_amp,
+            liquidity_ranges,
+            pmm_k,
+            oracle_lag,
+            oracle_noise,
+            fee_mode,
+            protocol_fee,
+            retail_exact_out_prob,
+            integer_math,
+            lp_schedule,
+            lp_arrival_rate,
+            lp_mean_size,
+            lp_sensitivity,
+            oracle_feed,
+            oracle_heartbeat,
+            oracle_deviation,
+            markout_horizons,
+            gas_price,
+            record_slots,
+            failure_policy,
+            failure_fee,
+            evm,
         }
     }
 
@@ -110,6 +280,143 @@ impl SimulationConfig {
     }
 }
 
+impl SimulationConfig {
+    /// Curve selection for both pools.
+    pub fn curve_params(&self) -> CurveParams {
+        CurveParams {
+            weight: self.pool_weight,
+            amplification: self.stableswap_amp,
+            ranges: self.liquidity_ranges.clone(),
+            pmm_k: self.pmm_k,
+        }
+    }
+}
+
+/// EVM execution settings for strategy contracts.
+///
+/// Strategies are deployed once per batch, so every simulation in a batch
+/// must use the same settings.
+#[pyclass]
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvmConfig {
+    /// Gas limit of the deployment transaction
+    #[pyo3(get, set)]
+    pub gas_limit_deploy: u64,
+    /// Gas limit of `getName`
+    #[pyo3(get, set)]
+    pub gas_limit_name: u64,
+    /// Gas limit of `afterInitialize` and `afterInitializeV2`
+    #[pyo3(get, set)]
+    pub gas_limit_initialize: u64,
+    /// Gas limit of `afterSwap` and `afterSwapV2`
+    #[pyo3(get, set)]
+    pub gas_limit_swap: u64,
+    /// Gas limit of `beforeSwap`, for trades and quotes alike
+    #[pyo3(get, set)]
+    pub gas_limit_before_swap: u64,
+    /// Gas limit of `onStep`
+    #[pyo3(get, set)]
+    pub gas_limit_on_step: u64,
+    /// Hardfork: "london", "merge", "shanghai", "cancun", "prague" or "latest"
+    #[pyo3(get, set)]
+    pub spec: String,
+    /// Maximum deployed code size in bytes (EIP-170); init code may be twice as large
+    #[pyo3(get, set)]
+    pub code_size_limit: usize,
+    /// Maximum memory a call may expand to, in bytes
+    #[pyo3(get, set)]
+    pub memory_limit: u64,
+    /// Abort callbacks that write storage outside the `slots` array or touch
+    /// other accounts (slower: every instruction is inspected)
+    #[pyo3(get, set)]
+    pub storage_guard: bool,
+}
+
+impl Default for EvmConfig {
+    fn default() -> Self {
+        Self {
+            gas_limit_deploy: 10_000_000,
+            gas_limit_name: 50_000,
+            gas_limit_initialize: 250_000,
+            gas_limit_swap: 250_000,
+            gas_limit_before_swap: 250_000,
+            gas_limit_on_step: 250_000,
+            spec: "latest".to_string(),
+            code_size_limit: 0x6000,
+            memory_limit: (1 << 32) - 1,
+            storage_guard: false,
+        }
+    }
+}
+
+#[pymethods]
+impl EvmConfig {
+    #[new]
+    #[pyo3(signature = (
+        gas_limit_deploy = 10_000_000,
+        gas_limit_name = 50_000,
+        gas_limit_initialize = 250_000,
+        gas_limit_swap = 250_000,
+        gas_limit_before_swap = 250_000,
+        gas_limit_on_step = 250_000,
+        spec = "latest".to_string(),
+        code_size_limit = 0x6000,
+        memory_limit = (1 << 32) - 1,
+        storage_guard = false
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        gas_limit_deploy: u64,
+        gas_limit_name: u64,
+        gas_limit_initialize: u64,
+        gas_limit_swap: u64,
+        gas_limit_before_swap: u64,
+        gas_limit_on_step: u64,
+        spec: String,
+        code_size_limit: usize,
+        memory_limit: u64,
+        storage_guard: bool,
+    ) -> Self {
+        Self {
+            gas_limit_deploy,
+            gas_limit_name,
+            gas_limit_initialize,
+            gas_limit_swap,
+            gas_limit_before_swap,
+            gas_limit_on_step,
+            spec,
+            code_size_limit,
+            memory_limit,
+            storage_guard,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "EvmConfig(spec={:?}, gas_limit_swap={})",
+            self.spec, self.gas_limit_swap
+        )
+    }
+}
+
+impl EvmConfig {
+    /// Parse `spec` into a revm hardfork.
+    pub fn spec_id(&self) -> Result<SpecId, String> {
+        match self.spec.as_str() {
+            "london" => Ok(SpecId::LONDON),
+            "merge" => Ok(SpecId::MERGE),
+            "shanghai" => Ok(SpecId::SHANGHAI),
+            "cancun" => Ok(SpecId::CANCUN),
+            "prague" => Ok(SpecId::PRAGUE),
+            "latest" => Ok(SpecId::LATEST),
+            other => Err(format!(
+                "unknown EVM spec '{}' (expected 'london', 'merge', 'shanghai', 'cancun', 'prague' or 'latest')",
+                other
+            )),
+        }
+    }
+}
+
 /// Pool configuration for multi-asset simulations.
 #[pyclass]
 #[derive(Debug, Clone)]
@@ -126,18 +433,55 @@ pub struct PoolConfigV2 {
     /// Initial reserve for token_b
     #[pyo3(get, set)]
     pub initial_b: f64,
+    /// Weight of token_a for a weighted product pool (None = constant product)
+    #[pyo3(get, set)]
+    pub weight: Option<f64>,
+    /// Amplification coefficient for a StableSwap pool (None = constant product)
+    #[pyo3(get, set)]
+    pub amplification: Option<f64>,
+    /// Concentrated liquidity ranges as (price_lower, price_upper, liquidity_weight),
+    /// with prices in token_b per token_a (empty = full-range curve)
+    #[pyo3(get, set)]
+    pub ranges: Vec<(f64, f64, f64)>,
+    /// Slippage parameter k for a PMM pool centred on the oracle cross rate
+    /// (None = constant product)
+    #[pyo3(get, set)]
+    pub pmm_k: Option<f64>,
 }
 
 #[pymethods]
 impl PoolConfigV2 {
     #[new]
-    #[pyo3(signature = (token_a, token_b, initial_a, initial_b))]
-    pub fn new(token_a: usize, token_b: usize, initial_a: f64, initial_b: f64) -> Self {
+    #[pyo3(signature = (
+        token_a,
+        token_b,
+        initial_a,
+        initial_b,
+        weight = None,
+        amplification = None,
+        ranges = Vec::new(),
+        pmm_k = None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        token_a: usize,
+        token_b: usize,
+        initial_a: f64,
+        initial_b: f64,
+        weight: Option<f64>,
+        amplification: Option<f64>,
+        ranges: Vec<(f64, f64, f64)>,
+        pmm_k: Option<f64>,
+    ) -> Self {
         Self {
             token_a,
             token_b,
             initial_a,
             initial_b,
+            weight,
+            amplification,
+            ranges,
+            pmm_k,
         }
     }
 
@@ -149,6 +493,18 @@ impl PoolConfigV2 {
     }
 }
 
+impl PoolConfigV2 {
+    /// Curve selection for this pool.
+    pub fn curve_params(&self) -> CurveParams {
+        CurveParams {
+            weight: self.weight,
+            amplification: self.amplification,
+            ranges: self.ranges.clone(),
+            pmm_k: self.pmm_k,
+        }
+    }
+}
+
 /// Configuration for a multi-asset simulation run.
 #[pyclass]
 #[derive(Debug, Clone)]
@@ -201,6 +557,86 @@ pub struct SimulationConfigV2 {
     /// Random seed for reproducibility (None = random)
     #[pyo3(get, set)]
     pub seed: Option<u64>,
+
+    /// Full pool configurations; when non-empty, used instead of `pools`
+    #[pyo3(get, set)]
+    pub pool_configs: Vec<PoolConfigV2>,
+
+    /// Fee accounting: "input" (separate bucket), "output" or "compounding" (V2 style)
+    #[pyo3(get, set)]
+    pub fee_mode: String,
+
+    /// Share of every swap fee paid to the protocol treasury, in [0, 1]
+    #[pyo3(get, set)]
+    pub protocol_fee: f64,
+
+    /// Probability that a retail order fixes the amount received (exact-output)
+    #[pyo3(get, set)]
+    pub retail_exact_out_prob: f64,
+
+    /// Run pools on WAD integers with on-chain rounding (constant product only)
+    #[pyo3(get, set)]
+    pub integer_math: bool,
+
+    /// Scheduled LP events in every pool: (step, fraction of share supply),
+    /// negative to withdraw
+    #[pyo3(get, set)]
+    pub lp_schedule: Vec<(u32, f64)>,
+
+    /// Expected stochastic LP events per step and pool (0 = scheduled events only)
+    #[pyo3(get, set)]
+    pub lp_arrival_rate: f64,
+
+    /// Mean stochastic LP event size, as a fraction of share supply
+    #[pyo3(get, set)]
+    pub lp_mean_size: f64,
+
+    /// How strongly LPs deposit after gains and withdraw after losses (0 = unbiased)
+    #[pyo3(get, set)]
+    pub lp_sensitivity: f64,
+
+    /// Publish each pool's oracle cross rate to its strategy (read with `oraclePrice()`)
+    #[pyo3(get, set)]
+    pub oracle_feed: bool,
+
+    /// Oracle delay in steps (oracle = fair prices `oracle_lag` steps ago)
+    #[pyo3(get, set)]
+    pub oracle_lag: u32,
+
+    /// Lognormal oracle noise (log-space sigma, 0 = exact)
+    #[pyo3(get, set)]
+    pub oracle_noise: f64,
+
+    /// Steps after which the feed publishes a round regardless of movement (0 = every step)
+    #[pyo3(get, set)]
+    pub oracle_heartbeat: u32,
+
+    /// Relative oracle move that publishes an early round (0 = every step)
+    #[pyo3(get, set)]
+    pub oracle_deviation: f64,
+
+    /// Horizons (in steps) at which trades are marked out against later fair prices
+    #[pyo3(get, set)]
+    pub markout_horizons: Vec<u32>,
+
+    /// Cost of one unit of strategy execution gas in the numeraire (Y for
+    /// single-pair runs), charged against PnL and edge (0 = gas is free)
+    #[pyo3(get, set)]
+    pub gas_price: f64,
+
+    /// Response to a failed strategy callback: "keep" the last fees, post
+    /// `failure_fee` ("default"), post the maximum fee ("max_fee") or end the
+    /// simulation early, reporting the strategy as disqualified ("disqualify")
+    #[pyo3(get, set)]
+    pub failure_policy: String,
+
+    /// Fee posted after a failure under the "default" policy
+    #[pyo3(get, set)]
+    pub failure_fee: f64,
+
+    /// EVM execution settings (gas limits, hardfork, code-size and memory limits)
+    #[pyo3(get, set)]
+    pub evm: EvmConfig,
 }
 
 #[pymethods]
@@ -218,8 +654,28 @@ impl SimulationConfigV2 {
         retail_buy_prob,
         numeraire_token,
         pools,
-        seed
+        seed,
+        pool_configs = Vec::new(),
+        fee_mode = "input".to_string(),
+        protocol_fee = 0.0,
+        retail_exact_out_prob = 0.0,
+        integer_math = false,
+        lp_schedule = Vec::new(),
+        lp_arrival_rate = 0.0,
+        lp_mean_size = 0.05,
+        lp_sensitivity = 0.0,
+        oracle_feed = false,
+        oracle_lag = 0,
+        oracle_noise = 0.0,
+        oracle_heartbeat = 0,
+        oracle_deviation = 0.0,
+        markout_horizons = vec![1, 10, 100],
+        gas_price = 0.0,
+        failure_policy = "keep".to_string(),
+        failure_fee = 0.003,
+        evm = EvmConfig::default()
     ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         n_steps: u32,
         initial_prices: Vec<f64>,
@@ -233,6 +689,25 @@ impl SimulationConfigV2 {
         numeraire_token: usize,
         pools: Vec<(usize, usize, f64, f64)>,
         seed: Option<u64>,
+        pool_configs: Vec<PoolConfigV2>,
+        fee_mode: String,
+        protocol_fee: f64,
+        retail_exact_out_prob: f64,
+        integer_math: bool,
+        lp_schedule: Vec<(u32, f64)>,
+        lp_arrival_rate: f64,
+        lp_mean_size: f64,
+        lp_sensitivity: f64,
+        oracle_feed: bool,
+        oracle_lag: u32,
+        oracle_noise: f64,
+        oracle_heartbeat: u32,
+        oracle_deviation: f64,
+        markout_horizons: Vec<u32>,
+        gas_price: f64,
+        failure_policy: String,
+        failure_fee: f64,
+        evm: EvmConfig,
     ) -> Self {
         Self {
             n_steps,
@@ -247,6 +722,25 @@ impl SimulationConfigV2 {
             numeraire_token,
             pools,
             seed,
+            pool_configs,
+            fee_mode,
+            protocol_fee,
+            retail_exact_out_prob,
+            integer_math,
+            lp_schedule,
+            lp_arrival_rate,
+            lp_mean_size,
+            lp_sensitivity,
+            oracle_feed,
+            oracle_lag,
+            oracle_noise,
+            oracle_heartbeat,
+            oracle_deviation,
+            markout_horizons,
+            gas_price,
+            failure_policy,
+            failure_fee,
+            evm,
         }
     }
 
@@ -255,12 +749,36 @@ impl SimulationConfigV2 {
             "SimulationConfigV2(n_steps={}, n_assets={}, n_pools={}, seed={:?})",
             self.n_steps,
             self.initial_prices.len(),
-            self.pools.len(),
+            self.resolved_pools().len(),
             self.seed
         )
     }
 }
 
+impl SimulationConfigV2 {
+    /// Pools to simulate: `pool_configs` if set, otherwise the `pools` tuples.
+    pub fn resolved_pools(&self) -> Vec<PoolConfigV2> {
+        if !self.pool_configs.is_empty() {
+            return self.pool_configs.clone();
+        }
+        self.pools
+            .iter()
+            .map(|&(token_a, token_b, initial_a, initial_b)| {
+                PoolConfigV2::new(
+                    token_a,
+                    token_b,
+                    initial_a,
+                    initial_b,
+                    None,
+                    None,
+                    Vec::new(),
+                    None,
+                )
+            })
+            .collect()
+    }
+}
+
 /// Configuration for hyperparameter variance across simulations.
 #[derive(Debug, Clone)]
 pub struct HyperparameterVariance {
@@ -317,6 +835,29 @@ impl HyperparameterVariance {
             retail_size_sigma: base.retail_size_sigma,
             retail_buy_prob: base.retail_buy_prob,
             seed: Some(seed),
+            pool_weight: base.pool_weight,
+            stableswap_amp: base.stableswap_amp,
+            liquidity_ranges: base.liquidity_ranges.clone(),
+            pmm_k: base.pmm_k,
+            oracle_lag: base.oracle_lag,
+            oracle_noise: base.oracle_noise,
+            fee_mode: base.fee_mode.clone(),
+            protocol_fee: base.protocol_fee,
+            retail_exact_out_prob: base.retail_exact_out_prob,
+            integer_math: base.integer_math,
+            lp_schedule: base.lp_schedule.clone(),
+            lp_arrival_rate: base.lp_arrival_rate,
+            lp_mean_size: base.lp_mean_size,
+            lp_sensitivity: base.lp_sensitivity,
+            oracle_feed: base.oracle_feed,
+            oracle_heartbeat: base.oracle_heartbeat,
+            oracle_deviation: base.oracle_deviation,
+            markout_horizons: base.markout_horizons.clone(),
+            gas_price: base.gas_price,
+            record_slots: base.record_slots,
+            failure_policy: base.failure_policy.clone(),
+            failure_fee: base.failure_fee,
+            evm: base.evm.clone(),
         }
     }
 }
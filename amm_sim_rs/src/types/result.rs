@@ -3,6 +3,8 @@
 use pyo3::prelude::*;
 use std::collections::HashMap;
 
+use crate::evm::CallbackTrace;
+
 /// Lightweight step result for charting (minimal memory footprint).
 #[pyclass]
 #[derive(Debug, Clone)]
@@ -26,6 +28,19 @@ pub struct LightweightStepResult {
     /// Fees (bid, ask) by strategy name
     #[pyo3(get)]
     pub fees: HashMap<String, (f64, f64)>,
+
+    /// Cumulative protocol fees by strategy name, valued in Y at this step's fair price
+    #[pyo3(get)]
+    pub treasury: HashMap<String, f64>,
+
+    /// Outstanding LP shares by strategy name
+    #[pyo3(get)]
+    pub lp_shares: HashMap<String, f64>,
+
+    /// Strategy `slots` values (read as int256) by strategy name; empty unless
+    /// `record_slots` is set
+    #[pyo3(get)]
+    pub slots: HashMap<String, Vec<f64>>,
 }
 
 #[pymethods]
@@ -38,6 +53,76 @@ impl LightweightStepResult {
     }
 }
 
+/// Gas summary of one strategy callback.
+#[pyclass]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasStats {
+    /// Number of calls
+    #[pyo3(get)]
+    pub count: usize,
+    /// Total gas used
+    #[pyo3(get)]
+    pub total: u64,
+    #[pyo3(get)]
+    pub min: u64,
+    #[pyo3(get)]
+    pub mean: f64,
+    #[pyo3(get)]
+    pub max: u64,
+    /// Nearest-rank percentiles
+    #[pyo3(get)]
+    pub p50: u64,
+    #[pyo3(get)]
+    pub p90: u64,
+    #[pyo3(get)]
+    pub p99: u64,
+}
+
+#[pymethods]
+impl GasStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "GasStats(count={}, mean={:.0}, max={})",
+            self.count, self.mean, self.max
+        )
+    }
+}
+
+/// Failed strategy callbacks over a simulation.
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FailureCounts {
+    /// Calls that reverted
+    #[pyo3(get)]
+    pub reverts: u32,
+    /// Calls that ran out of gas
+    #[pyo3(get)]
+    pub out_of_gas: u32,
+    /// Calls that halted otherwise, returned undecodable data, or were
+    /// stopped by the storage guard
+    #[pyo3(get)]
+    pub invalid: u32,
+}
+
+impl FailureCounts {
+    /// Add another pool's counts.
+    pub fn merge(&mut self, other: &FailureCounts) {
+        self.reverts += other.reverts;
+        self.out_of_gas += other.out_of_gas;
+        self.invalid += other.invalid;
+    }
+}
+
+#[pymethods]
+impl FailureCounts {
+    fn __repr__(&self) -> String {
+        format!(
+            "FailureCounts(reverts={}, out_of_gas={}, invalid={})",
+            self.reverts, self.out_of_gas, self.invalid
+        )
+    }
+}
+
 /// Lightweight simulation result for charting.
 #[pyclass]
 #[derive(Debug, Clone)]
@@ -50,11 +135,11 @@ pub struct LightweightSimResult {
     #[pyo3(get)]
     pub strategies: Vec<String>,
 
-    /// Final PnL by strategy name
+    /// Final PnL of the initial LP position by strategy name
     #[pyo3(get)]
     pub pnl: HashMap<String, f64>,
 
-    /// Edge by strategy name (sum over trades)
+    /// Edge by strategy name (sum over trades, net of protocol fees)
     #[pyo3(get)]
     pub edges: HashMap<String, f64>,
 
@@ -81,6 +166,61 @@ pub struct LightweightSimResult {
     /// Average fees (bid, ask) by strategy name over the simulation
     #[pyo3(get)]
     pub average_fees: HashMap<String, (f64, f64)>,
+
+    /// Protocol fees collected by strategy name: (fees_x, fees_y)
+    #[pyo3(get)]
+    pub protocol_fees: HashMap<String, (f64, f64)>,
+
+    /// Protocol fees by strategy name, valued in Y at the final fair price
+    #[pyo3(get)]
+    pub treasury: HashMap<String, f64>,
+
+    /// Final PnL per LP share (in Y) by strategy name
+    #[pyo3(get)]
+    pub pnl_per_share: HashMap<String, f64>,
+
+    /// Edge per LP share by strategy name (each trade's edge over the shares outstanding)
+    #[pyo3(get)]
+    pub edges_per_share: HashMap<String, f64>,
+
+    /// Final LP share supply by strategy name
+    #[pyo3(get)]
+    pub lp_shares: HashMap<String, f64>,
+
+    /// Loss-versus-rebalancing (in Y) by strategy name
+    #[pyo3(get)]
+    pub lvr: HashMap<String, f64>,
+
+    /// Summed arbitrage markouts as (horizon, markout in Y) by strategy name
+    #[pyo3(get)]
+    pub arb_markouts: HashMap<String, Vec<(u32, f64)>>,
+
+    /// Summed retail markouts as (horizon, markout in Y) by strategy name
+    #[pyo3(get)]
+    pub retail_markouts: HashMap<String, Vec<(u32, f64)>>,
+
+    /// Gas per callback ("afterInitialize", "afterSwap", ...) by strategy name
+    #[pyo3(get)]
+    pub gas: HashMap<String, HashMap<String, GasStats>>,
+
+    /// Gas cost (in Y) by strategy name, charged each step to the shares
+    /// outstanding: it is already deducted from `edges`, the per-share values,
+    /// `pnl` and the step `pnls`
+    #[pyo3(get)]
+    pub gas_cost: HashMap<String, f64>,
+
+    /// Failed callbacks by strategy name
+    #[pyo3(get)]
+    pub failures: HashMap<String, FailureCounts>,
+
+    /// Failure that disqualified a strategy, by strategy name; the simulation
+    /// ended after the step it happened in
+    #[pyo3(get)]
+    pub disqualified: HashMap<String, String>,
+
+    /// Callback traces by strategy name (only for strategies with tracing enabled)
+    #[pyo3(get)]
+    pub traces: HashMap<String, Vec<CallbackTrace>>,
 }
 
 #[pymethods]
@@ -222,6 +362,21 @@ pub struct PoolStateV2 {
     pub reserve_a: f64,
     #[pyo3(get)]
     pub reserve_b: f64,
+    /// Protocol fees collected in token_a
+    #[pyo3(get)]
+    pub protocol_fee_a: f64,
+    /// Protocol fees collected in token_b
+    #[pyo3(get)]
+    pub protocol_fee_b: f64,
+    /// Final LP share supply
+    #[pyo3(get)]
+    pub lp_shares: f64,
+    /// Final PnL per LP share in numeraire
+    #[pyo3(get)]
+    pub pnl_per_share: f64,
+    /// Edge per LP share in numeraire (each trade's edge over the shares outstanding)
+    #[pyo3(get)]
+    pub edge_per_share: f64,
 }
 
 #[pymethods]
@@ -246,11 +401,12 @@ pub struct LightweightSimResultV2 {
     #[pyo3(get)]
     pub strategies: Vec<String>,
 
-    /// Final PnL in numeraire by strategy name
+    /// Final PnL in numeraire of the initial LP positions by strategy name,
+    /// summed over pools
     #[pyo3(get)]
     pub pnl: HashMap<String, f64>,
 
-    /// Edge in numeraire by strategy name
+    /// Edge in numeraire by strategy name (net of protocol fees)
     #[pyo3(get)]
     pub edges: HashMap<String, f64>,
 
@@ -258,9 +414,43 @@ pub struct LightweightSimResultV2 {
     #[pyo3(get)]
     pub final_prices: Vec<f64>,
 
-    /// Final reserves by pool id
+    /// Final reserves and per-share results by pool id
     #[pyo3(get)]
     pub pools: Vec<PoolStateV2>,
+
+    /// Protocol fees in numeraire by strategy name, valued at final prices
+    #[pyo3(get)]
+    pub treasury: HashMap<String, f64>,
+
+    /// Loss-versus-rebalancing in numeraire by strategy name, summed over pools
+    #[pyo3(get)]
+    pub lvr: HashMap<String, f64>,
+
+    /// Summed arbitrage markouts as (horizon, markout in numeraire) by strategy name
+    #[pyo3(get)]
+    pub arb_markouts: HashMap<String, Vec<(u32, f64)>>,
+
+    /// Summed retail markouts as (horizon, markout in numeraire) by strategy name
+    #[pyo3(get)]
+    pub retail_markouts: HashMap<String, Vec<(u32, f64)>>,
+
+    /// Gas per callback ("afterInitialize", "afterSwapV2", ...) by strategy name,
+    /// over all pools
+    #[pyo3(get)]
+    pub gas: HashMap<String, HashMap<String, GasStats>>,
+
+    /// Gas cost in numeraire by strategy name, already deducted from `pnl` and `edges`
+    #[pyo3(get)]
+    pub gas_cost: HashMap<String, f64>,
+
+    /// Failed callbacks by strategy name, over all pools
+    #[pyo3(get)]
+    pub failures: HashMap<String, FailureCounts>,
+
+    /// Failure that disqualified a strategy, by strategy name; the simulation
+    /// ended after the step it happened in
+    #[pyo3(get)]
+    pub disqualified: HashMap<String, String>,
 }
 
 #[pymethods]
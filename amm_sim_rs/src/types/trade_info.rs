@@ -137,6 +137,12 @@ pub const SELECTOR_AFTER_INITIALIZE_V2: [u8; 4] = [0x1a, 0xfe, 0xf3, 0x59];
 /// Function selector for afterSwapV2(TradeInfoV2)
 pub const SELECTOR_AFTER_SWAP_V2: [u8; 4] = [0x7b, 0xca, 0x99, 0xa5];
 
+/// Function selector for beforeSwap(bool,uint256,uint256,uint256)
+pub const SELECTOR_BEFORE_SWAP: [u8; 4] = [0xc4, 0x0c, 0x1b, 0x95];
+
+/// Function selector for onStep(uint256,uint256,uint256)
+pub const SELECTOR_ON_STEP: [u8; 4] = [0xa8, 0x73, 0x37, 0x67];
+
 /// Function selector for getName()
 pub const SELECTOR_GET_NAME: [u8; 4] = [0x17, 0xd7, 0xde, 0x7c];
 
@@ -176,6 +182,53 @@ pub fn encode_after_initialize_v2(
     buffer
 }
 
+/// Encode beforeSwap(bool,uint256,uint256,uint256) calldata.
+///
+/// Arguments: isBuy (AMM buys X), amountIn, reserveX, reserveY.
+#[inline]
+pub fn encode_before_swap(
+    is_buy: bool,
+    amount_in: Wad,
+    reserve_x: Wad,
+    reserve_y: Wad,
+) -> [u8; 132] {
+    let mut buffer = [0u8; 132];
+    buffer[0..4].copy_from_slice(&SELECTOR_BEFORE_SWAP);
+    if is_buy {
+        buffer[35] = 1;
+    }
+    encode_u256(&mut buffer[36..68], amount_in.raw() as u128);
+    encode_u256(&mut buffer[68..100], reserve_x.raw() as u128);
+    encode_u256(&mut buffer[100..132], reserve_y.raw() as u128);
+    buffer
+}
+
+/// Encode onStep(uint256,uint256,uint256) calldata.
+///
+/// Arguments: timestamp, reserveX, reserveY.
+#[inline]
+pub fn encode_on_step(timestamp: u64, reserve_x: Wad, reserve_y: Wad) -> [u8; 100] {
+    let mut buffer = [0u8; 100];
+    buffer[0..4].copy_from_slice(&SELECTOR_ON_STEP);
+    encode_u256(&mut buffer[4..36], timestamp as u128);
+    encode_u256(&mut buffer[36..68], reserve_x.raw() as u128);
+    encode_u256(&mut buffer[68..100], reserve_y.raw() as u128);
+    buffer
+}
+
+/// Decode a single uint256 return value as a fee in WAD.
+#[inline]
+pub fn decode_fee(data: &[u8]) -> Option<Wad> {
+    if data.len() < 32 {
+        return None;
+    }
+    let fee = decode_u256(&data[0..32])?;
+    if fee > MAX_FEE as u128 {
+        return None;
+    }
+    Some(Wad::new(i128::try_from(fee).ok()?))
+}
+
 /// Decode (uint256, uint256) return value as (bid_fee, ask_fee) in WAD.
 #[inline]
 pub fn decode_fee_pair(data: &[u8]) -> Option<(Wad, Wad)> {
@@ -300,4 +353,31 @@ mod tests {
         assert_eq!(&buffer[0..4], &SELECTOR_AFTER_SWAP_V2);
         assert_eq!(buffer[35], 0);
     }
+
+    #[test]
+    fn test_encode_before_swap() {
+        let calldata = encode_before_swap(
+            true,
+            Wad::new(WAD),
+            Wad::new(WAD * 100),
+            Wad::new(WAD * 200),
+        );
+        assert_eq!(&calldata[0..4], &SELECTOR_BEFORE_SWAP);
+        assert_eq!(calldata[35], 1);
+        assert_eq!(decode_u256(&calldata[68..100]).unwrap() as i128, WAD * 100);
+
+        let mut data = [0u8; 32];
+        data[16..32].copy_from_slice(&(MAX_FEE as u128).to_be_bytes());
+        assert_eq!(decode_fee(&data), Some(Wad::new(MAX_FEE)));
+        data[31] += 1;
+        assert!(decode_fee(&data).is_none());
+    }
+
+    #[test]
+    fn test_encode_on_step() {
+        let calldata = encode_on_step(42, Wad::new(WAD * 100), Wad::new(WAD * 200));
+        assert_eq!(&calldata[0..4], &SELECTOR_ON_STEP);
+        assert_eq!(decode_u256(&calldata[4..36]).unwrap(), 42);
+        assert_eq!(decode_u256(&calldata[68..100]).unwrap() as i128, WAD * 200);
+    }
 }
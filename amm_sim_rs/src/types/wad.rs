@@ -69,7 +69,7 @@ impl Wad {
     /// Clamp fee to valid range [0, MAX_FEE].
     #[inline]
     pub fn clamp_fee(self) -> Wad {
-        Wad(self.0.max(0).min(MAX_FEE))
+        Wad(self.0.clamp(0, MAX_FEE))
     }
 
     /// Clamp to arbitrary range.
@@ -213,7 +213,7 @@ mod tests {
         assert_eq!(w.0, WAD / 2);
 
         let w = Wad::from_f64(0.0025); // 25 bps
-        assert_eq!(w.0, 25 * BPS / 10);
+        assert_eq!(w.0, 25 * BPS);
     }
 
     #[test]
@@ -249,7 +249,7 @@ mod tests {
 
         let a = Wad::from_f64(2.0);
         let s = a.sqrt();
-        assert!((s.to_f64() - 1.414213562).abs() < 1e-6);
+        assert!((s.to_f64() - std::f64::consts::SQRT_2).abs() < 1e-6);
     }
 
     #[test]
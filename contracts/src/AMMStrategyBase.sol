@@ -23,6 +23,10 @@ abstract contract AMMStrategyBase is IAMMStrategy {
     /// @notice 1 basis point in WAD (0.01% = 0.0001 = 1e14)
     uint256 public constant BPS = 1e14;
 
+    /// @dev Storage slots the simulator writes the oracle feed into
+    bytes32 internal constant ORACLE_PRICE_SLOT = keccak256("amm.oracle.price");
+    bytes32 internal constant ORACLE_UPDATED_AT_SLOT = keccak256("amm.oracle.updatedAt");
+
     /*//////////////////////////////////////////////////////////////
                             STORAGE SLOTS
     //////////////////////////////////////////////////////////////*/
@@ -124,4 +128,21 @@ abstract contract AMMStrategyBase is IAMMStrategy {
         require(index < 32, "Slot index out of bounds");
         slots[index] = value;
     }
+
+    /*//////////////////////////////////////////////////////////////
+                            ORACLE FEED
+    //////////////////////////////////////////////////////////////*/
+
+    /// @notice Latest oracle round, when the simulation enables the oracle feed
+    /// @dev Like a Chainlink feed, the price may be lagged, noisy and stale
+    /// @return price Oracle price of X in Y (WAD precision), 0 if the feed is disabled
+    /// @return updatedAt Step at which the round was published
+    function oraclePrice() internal view returns (uint256 price, uint256 updatedAt) {
+        bytes32 priceSlot = ORACLE_PRICE_SLOT;
+        bytes32 updatedAtSlot = ORACLE_UPDATED_AT_SLOT;
+        assembly {
+            price := sload(priceSlot)
+            updatedAt := sload(updatedAtSlot)
+        }
+    }
 }
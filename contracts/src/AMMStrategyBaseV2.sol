@@ -18,6 +18,10 @@ abstract contract AMMStrategyBaseV2 is IAMMStrategyV2 {
     /// @notice 1 basis point in WAD (0.01% = 0.0001 = 1e14)
     uint256 public constant BPS = 1e14;
 
+    /// @dev Storage slots the simulator writes the oracle feed into
+    bytes32 internal constant ORACLE_PRICE_SLOT = keccak256("amm.oracle.price");
+    bytes32 internal constant ORACLE_UPDATED_AT_SLOT = keccak256("amm.oracle.updatedAt");
+
     /// @notice Fixed storage array - strategies can only use these 32 slots
     uint256[32] public slots;
 
@@ -70,4 +74,15 @@ abstract contract AMMStrategyBaseV2 is IAMMStrategyV2 {
         require(index < 32, "Slot index out of bounds");
         slots[index] = value;
     }
+
+    /// @notice Latest oracle round for this pool: price of token A in token B (WAD,
+    ///         0 if the feed is disabled) and the step it was published
+    function oraclePrice() internal view returns (uint256 price, uint256 updatedAt) {
+        bytes32 priceSlot = ORACLE_PRICE_SLOT;
+        bytes32 updatedAtSlot = ORACLE_UPDATED_AT_SLOT;
+        assembly {
+            price := sload(priceSlot)
+            updatedAt := sload(updatedAtSlot)
+        }
+    }
 }
@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.24;
+
+/// @title Optional beforeSwap fee hook
+/// @notice Strategies that also implement this are asked for the fee of each
+///         trade before it executes, in the style of Uniswap V4 dynamic-fee hooks.
+///         The returned fee replaces the posted bid/ask fee for that trade only.
+/// @dev Fees are WAD values (1e18 = 100%), capped at 10%. Quotes call the hook
+///      without committing state; executions call it and keep any state written.
+interface IBeforeSwapHook {
+    /// @param isBuy true if the AMM buys X (trader sells X)
+    /// @param amountIn Trader input amount (WAD precision), in X if isBuy else Y
+    /// @param reserveX Current X reserves (WAD precision)
+    /// @param reserveY Current Y reserves (WAD precision)
+    /// @return fee Fee for this trade (WAD precision)
+    function beforeSwap(bool isBuy, uint256 amountIn, uint256 reserveX, uint256 reserveY)
+        external
+        returns (uint256 fee);
+}
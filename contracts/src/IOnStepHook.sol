@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.24;
+
+/// @title Optional per-step heartbeat
+/// @notice Strategies that also implement this are called once per simulation
+///         step, whether or not a trade happened, so fees can decay back to a
+///         base level during quiet periods.
+/// @dev Fees are WAD values (1e18 = 100%), capped at 10%. The returned pair
+///      replaces the posted fees, like afterSwap.
+interface IOnStepHook {
+    /// @param timestamp Current simulation step
+    /// @param reserveX Current X reserves (WAD precision)
+    /// @param reserveY Current Y reserves (WAD precision)
+    /// @return bidFee Fee when AMM buys X (WAD precision)
+    /// @return askFee Fee when AMM sells X (WAD precision)
+    function onStep(uint256 timestamp, uint256 reserveX, uint256 reserveY)
+        external
+        returns (uint256 bidFee, uint256 askFee);
+}
@@ -59,6 +59,70 @@ class TestMatchRunner:
         assert result.strategy_a == "Vanilla_30bps"
         assert result.strategy_b == "Vanilla_30bps"
 
+    def test_build_configs_forwards_base_config(self):
+        config = build_base_config(seed=None)
+        config.pool_weight = 0.8
+        config.stableswap_amp = 50.0
+        config.liquidity_ranges = [(90.0, 110.0, 1.0)]
+        config.pmm_k = 0.5
+        config.oracle_lag = 3
+        config.oracle_noise = 0.01
+        config.fee_mode = "output"
+        config.protocol_fee = 0.2
+        config.retail_exact_out_prob = 0.25
+        config.integer_math = True
+        config.lp_schedule = [(10, 0.5)]
+        config.lp_arrival_rate = 0.1
+        config.lp_mean_size = 0.05
+        config.lp_sensitivity = 2.0
+        config.oracle_feed = True
+        config.oracle_heartbeat = 5
+        config.oracle_deviation = 0.002
+        config.markout_horizons = [1, 5]
+        config.gas_price = 1e-9
+        config.record_slots = True
+        config.failure_policy = "max_fee"
+        config.failure_fee = 0.01
+        config.evm = amm_sim_rs.EvmConfig(gas_limit_swap=100_000)
+        runner = MatchRunner(
+            n_simulations=2, config=config, n_workers=1, variance=BASELINE_VARIANCE
+        )
+
+        configs = runner._build_configs()
+        assert [cfg.seed for cfg in configs] == [0, 1]
+        for cfg in configs:
+            assert cfg.pool_weight == 0.8
+            assert cfg.stableswap_amp == 50.0
+            assert cfg.liquidity_ranges == [(90.0, 110.0, 1.0)]
+            assert cfg.pmm_k == 0.5
+            assert (cfg.oracle_lag, cfg.oracle_noise) == (3, 0.01)
+            assert cfg.fee_mode == "output"
+            assert cfg.protocol_fee == 0.2
+            assert cfg.retail_exact_out_prob == 0.25
+            assert cfg.integer_math is True
+            assert cfg.lp_schedule == [(10, 0.5)]
+            assert cfg.lp_arrival_rate == 0.1
+            assert cfg.lp_mean_size == 0.05
+            assert cfg.lp_sensitivity == 2.0
+            assert cfg.oracle_feed is True
+            assert cfg.oracle_heartbeat == 5
+            assert cfg.oracle_deviation == 0.002
+            assert cfg.markout_horizons == [1, 5]
+            assert cfg.gas_price == 1e-9
+            assert cfg.record_slots is True
+            assert cfg.failure_policy == "max_fee"
+            assert cfg.failure_fee == 0.01
+            assert cfg.evm.gas_limit_swap == 100_000
+
+    def test_disqualified_strategy_loses_the_simulation(self):
+        from amm_competition.competition.match import _sim_outcome
+
+        reason = "afterSwap reverted at step 3"
+        assert _sim_outcome(2.0, 1.0, {}) == 1
+        assert _sim_outcome(1.0, 2.0, {"normalizer": reason}) == 1
+        assert _sim_outcome(2.0, 1.0, {"submission": reason}) == -1
+        assert _sim_outcome(2.0, 1.0, {"submission": reason, "normalizer": reason}) == 0
+
     def test_match_winner(self, vanilla_bytecode_and_abi):
         from amm_competition.evm.adapter import EVMStrategyAdapter
 
@@ -174,6 +238,7 @@ class TestMatchRunner:
         result = runner.run_match(strategy_a, strategy_b, store_results=True)
 
         assert len(result.simulation_results) == 3
+        assert all(sim.disqualified == {} for sim in result.simulation_results)
 
     def test_same_name_strategies_no_collision(self, vanilla_bytecode_and_abi):
         """Test that strategies with the same getName() don't cause HashMap collision."""
@@ -283,6 +348,26 @@ class TestMatchRunnerV2:
         # 3 configured pools x 2 strategies = 6 pool instances
         assert len(first.pools) == 6
 
+    def test_run_match_v2_pmm_pool(self, vanilla_bytecode_and_abi):
+        from amm_competition.evm.adapter import EVMStrategyAdapter
+
+        configs = self._build_v2_configs(2)
+        for cfg in configs:
+            cfg.oracle_lag = 2
+            cfg.pool_configs = [
+                amm_sim_rs.PoolConfigV2(0, 1, 10_000.0, 100.0, pmm_k=0.5),
+                amm_sim_rs.PoolConfigV2(0, 2, 10_000.0, 66.6667),
+            ]
+
+        bytecode, abi = vanilla_bytecode_and_abi
+        strategy_a = EVMStrategyAdapter(bytecode=bytecode, abi=abi)
+        strategy_b = EVMStrategyAdapter(bytecode=bytecode, abi=abi)
+        runner = MatchRunnerV2(configs=configs, n_workers=1)
+        result = runner.run_match(strategy_a, strategy_b, store_results=True)
+
+        assert result.total_games == 2
+        assert len(result.simulation_results[0].pools) == 4
+
     def test_from_legacy_builder(self, vanilla_bytecode_and_abi):
         from amm_competition.evm.adapter import EVMStrategyAdapter
 
@@ -330,3 +415,40 @@ class TestConfigV2Compatibility:
             assert cfg.numeraire_token == 1
             assert cfg.initial_prices == [base.initial_price, 1.0]
             assert cfg.pools == [(0, 1, base.initial_x, base.initial_y)]
+
+    def test_build_v2_configs_from_legacy_forwards_curve(self):
+        base = build_base_config(seed=7)
+        base.pool_weight = 0.8
+        base.stableswap_amp = 50.0
+        base.liquidity_ranges = [(90.0, 110.0, 1.0)]
+        base.pmm_k = 0.5
+        (cfg,) = build_v2_configs_from_legacy(
+            base_config=base,
+            n_simulations=1,
+            variance=BASELINE_VARIANCE,
+        )
+
+        (pool,) = cfg.pool_configs
+        assert (pool.token_a, pool.token_b) == (0, 1)
+        assert (pool.initial_a, pool.initial_b) == (base.initial_x, base.initial_y)
+        assert pool.weight == 0.8
+        assert pool.amplification == 50.0
+        assert pool.ranges == [(90.0, 110.0, 1.0)]
+        assert pool.pmm_k == 0.5
+
+    def test_build_v2_configs_from_legacy_forwards_lp_flow(self):
+        base = build_base_config(seed=7)
+        base.lp_schedule = [(10, 0.5)]
+        base.lp_arrival_rate = 0.1
+        base.lp_mean_size = 0.05
+        base.lp_sensitivity = 2.0
+        (cfg,) = build_v2_configs_from_legacy(
+            base_config=base,
+            n_simulations=1,
+            variance=BASELINE_VARIANCE,
+        )
+
+        assert cfg.lp_schedule == [(10, 0.5)]
+        assert cfg.lp_arrival_rate == 0.1
+        assert cfg.lp_mean_size == 0.05
+        assert cfg.lp_sensitivity == 2.0